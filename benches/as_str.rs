@@ -0,0 +1,47 @@
+//! Measures the `as_str`/`len`/`as_bytes` hot path over a mix of inline, heap and static strings,
+//! shuffled so the representation branch can't be predicted.
+//! Run with `cargo bench --bench as_str`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use sso_string::SsoString;
+
+const NUM_STRINGS: usize = 100_000;
+
+fn make_strings() -> Vec<SsoString> {
+    static LONG: &str = "a static string that is longer than the inline capacity";
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    (0..NUM_STRINGS)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            match state % 3 {
+                0 => SsoString::from(format!("inline {}", i % 1000)),
+                1 => SsoString::from(format!("a heap string with a number {i:>10} in it")),
+                _ => SsoString::from_static(LONG),
+            }
+        })
+        .collect()
+}
+
+fn bench_as_str(c: &mut Criterion) {
+    let strings = make_strings();
+
+    let mut group = c.benchmark_group("as_str mixed representations");
+    group.throughput(Throughput::Elements(NUM_STRINGS as u64));
+    group.bench_function("len()", |b| {
+        b.iter(|| strings.iter().map(|s| black_box(s).len()).sum::<usize>())
+    });
+    group.bench_function("as_str().len()", |b| {
+        b.iter(|| strings.iter().map(|s| black_box(s).as_str().len()).sum::<usize>())
+    });
+    group.bench_function("as_bytes().first()", |b| {
+        b.iter(|| strings.iter().map(|s| black_box(s).as_bytes().first().copied().unwrap_or(0) as usize).sum::<usize>())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_as_str);
+criterion_main!(benches);
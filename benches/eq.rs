@@ -0,0 +1,47 @@
+//! Compares `SsoString == SsoString` against plain `str` equality.
+//! Run with `cargo bench --bench eq` and again with `--features simd`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sso_string::SsoString;
+
+const NUM_STRINGS: usize = 100_000;
+
+fn make_strings(len: usize) -> Vec<String> {
+    (0..NUM_STRINGS)
+        .map(|i| {
+            let mut s = format!("{i:0>width$}", width = len);
+            s.truncate(len);
+            s
+        })
+        .collect()
+}
+
+fn bench_eq(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eq");
+    group.throughput(Throughput::Elements(NUM_STRINGS as u64));
+
+    for (label, len) in [("inline", 20), ("heap", 64), ("heap", 1024)] {
+        let strings = make_strings(len);
+        let sso_a: Vec<SsoString> = strings.iter().map(SsoString::from).collect();
+        let sso_b = sso_a.clone();
+        let std_a = strings.clone();
+        let std_b = strings;
+        let param = format!("{label} {len}B");
+
+        group.bench_with_input(BenchmarkId::new("SsoString", &param), &(&sso_a, &sso_b), |b, (sso_a, sso_b)| {
+            b.iter(|| sso_a.iter().zip(sso_b.iter()).filter(|(a, b)| black_box(a) == black_box(b)).count())
+        });
+        group.bench_with_input(BenchmarkId::new("as_str()", &param), &(&sso_a, &sso_b), |b, (sso_a, sso_b)| {
+            b.iter(|| sso_a.iter().zip(sso_b.iter()).filter(|(a, b)| black_box(a.as_str()) == black_box(b.as_str())).count())
+        });
+        group.bench_with_input(BenchmarkId::new("String", &param), &(&std_a, &std_b), |b, (std_a, std_b)| {
+            b.iter(|| std_a.iter().zip(std_b.iter()).filter(|(a, b)| black_box(a) == black_box(b)).count())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_eq);
+criterion_main!(benches);
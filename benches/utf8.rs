@@ -0,0 +1,38 @@
+//! Compares `SsoString::from_utf8` against `from_utf8_simd` on multi-megabyte input.
+//! Run with `cargo bench --bench utf8 --features simd`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use sso_string::SsoString;
+
+const SIZE: usize = 8 * 1024 * 1024;
+
+fn make_input(unit: &str) -> Vec<u8> {
+    unit.repeat(SIZE / unit.len()).into_bytes()
+}
+
+fn bench_utf8(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_utf8");
+    group.sample_size(20);
+
+    for (label, unit) in [
+        ("ascii", "The quick brown fox jumps over the lazy dog. "),
+        ("mostly ascii", "The quick brown fox jumps over the lazy dog, café. "),
+        ("non-ascii", "Съешь же ещё этих мягких французских булок. "),
+    ] {
+        let input = make_input(unit);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("from_utf8", label), &input, |b, input| {
+            b.iter_batched(|| input.clone(), |bytes| SsoString::from_utf8(black_box(bytes)).is_ok(), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("from_utf8_simd", label), &input, |b, input| {
+            b.iter_batched(|| input.clone(), |bytes| SsoString::from_utf8_simd(black_box(bytes)).is_ok(), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_utf8);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+//! Deduplicating string storage with cheap `Copy` handles.
+
+use std::collections::HashMap;
+
+use crate::{Representation, SsoString};
+
+/// Handle to a string stored in an `Interner`. Only meaningful for the interner that returned it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InternedId(u32);
+
+/// Stores each distinct string once. Short strings live inline in the table; for longer ones the
+/// lookup key borrows the stored string's heap buffer instead of holding a second copy.
+#[derive(Debug, Default)]
+pub struct Interner {
+    ids: HashMap<SsoString, InternedId>,
+    strings: Vec<SsoString>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of `s`, storing it first if it hasn't been interned yet.
+    ///
+    /// # Panics
+    /// Panics if more than `u32::MAX` distinct strings are interned.
+    pub fn intern(&mut self, s: &str) -> InternedId {
+        if let Some(&id) = self.ids.get(s) {
+            return id;
+        }
+
+        let id = InternedId(u32::try_from(self.strings.len()).expect("too many interned strings"));
+        let string = SsoString::from(s);
+        let key = match string.representation() {
+            // SAFETY: heap buffers don't move when `strings` reallocates, and stored strings are
+            // never mutated or dropped before the map
+            Representation::Heap => unsafe { SsoString::from_static_unchecked(string.as_str()) },
+            _ => string.clone(),
+        };
+        self.strings.push(string);
+        self.ids.insert(key, id);
+        id
+    }
+
+    /// The id of `s` if it has been interned, without storing it.
+    pub fn get(&self, s: &str) -> Option<InternedId> {
+        self.ids.get(s).copied()
+    }
+
+    /// The string behind `id`.
+    ///
+    /// # Panics
+    /// Panics if `id` came from a different interner with more strings.
+    pub fn resolve(&self, id: InternedId) -> &str {
+        self.strings[id.0 as usize].as_str()
+    }
+
+    /// Number of distinct strings interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
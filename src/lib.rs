@@ -1,4 +1,18 @@
-use std::{alloc::{self, Layout}, fmt::{self, Debug}, hash::Hash, hint, mem, slice, str};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod intern;
+
+use alloc::{
+    alloc::{alloc, dealloc, handle_alloc_error, realloc, Layout},
+    borrow::Cow,
+    boxed::Box,
+    string::String,
+    vec::Vec,
+};
+use core::{borrow::Borrow, fmt::{self, Debug}, hash::Hash, hint, iter::FusedIterator, mem, ops::{Bound, RangeBounds}, ptr, slice::{self, SliceIndex}, str};
 
 #[derive(Debug, Clone, Copy)]
 pub enum SsosPrecond {
@@ -42,20 +56,109 @@ pub type SsosPrecondType = u32;
 
 
 
-/// Bits 0-4: Length of the string (if inlined)
-/// Bit 5: Empty (reserved for future use??)
+/// Bits 0-4: Length of the string (if inlined), low bits
+/// Bit 5: for inline strings, the high bit of the length (lengths up to 63). For static strings (never inlined),
+/// flag IS_STATIC_LIFETIME instead: set when the data came from `from_static` and really is `'static`, clear for `from_static_unchecked`.
+/// Always 0 for heap strings.
 /// Bit 6: flag IS_STATIC. Any operations requiring mutable state need to copy the underlying data if this bit is set to 1 (this flag is irrelevent for inlined strings). Enables us to do CoW optimizations. 
 /// Bit 7: IS_INLINED. 
 struct SsoStrMetadata {
     data: u8,
 }
 
-pub struct SsoString {
+/// Error returned when heap capacity can't be reserved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested capacity overflows `usize`, exceeds `isize::MAX` or doesn't fit the shifted capacity field.
+    CapacityOverflow,
+    /// The allocator returned null for this layout.
+    AllocError { layout: Layout },
+}
+
+impl TryReserveError {
+    /// What the infallible paths do on failure, mirroring `Vec`.
+    fn handle(self) -> ! {
+        match self {
+            Self::CapacityOverflow => panic!("capacity overflow"),
+            Self::AllocError { layout } => handle_alloc_error(layout),
+        }
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CapacityOverflow => f.write_str("memory allocation failed because the computed capacity exceeded the maximum"),
+            Self::AllocError { .. } => f.write_str("memory allocation failed because the memory allocator returned an error"),
+        }
+    }
+}
+
+impl core::error::Error for TryReserveError {}
+
+/// Error returned by `SsoString::from_utf16` when the input contains an unpaired surrogate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromUtf16Error(());
+
+impl fmt::Display for FromUtf16Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid utf-16: lone surrogate found")
+    }
+}
+
+impl core::error::Error for FromUtf16Error {}
+
+/// Which backing store a `SsoString` currently uses. Exactly one applies at any time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Representation {
+    /// Stored in the struct itself, no allocation.
+    Inline,
+    /// An owned heap buffer.
+    Heap,
+    /// Borrowed read-only data, copied on the first write.
+    Static,
+}
+
+/// The heap/static view. `repr(C)` keeps `capacity` first, so its low byte (left free by the `<< 8`
+/// encoding) doubles as the metadata byte.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct HeapRepr {
     capacity: usize,
     length: usize,
     pointer: *mut u8,
 }
 
+/// The inline view: the shared metadata byte followed by the string bytes.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct InlineRepr<const N: usize> {
+    metadata: u8,
+    bytes: [u8; N],
+}
+
+/// Every byte of the union is kept initialized (strings start zeroed), so either view can be read.
+#[repr(C)]
+union Repr<const N: usize> {
+    heap: HeapRepr,
+    inline: InlineRepr<N>,
+}
+
+/// Inline capacity of `SsoString`: everything but the metadata byte of the heap view, 23 bytes on
+/// 64-bit targets and 11 on 32-bit.
+pub const DEFAULT_INLINE_CAPACITY: usize = mem::size_of::<HeapRepr>() - 1;
+
+/// A small-string-optimized string that stores up to `N` bytes inline (at least
+/// `DEFAULT_INLINE_CAPACITY`, at most 63). Larger `N` grows the struct to `N + 1` bytes, rounded up
+/// to pointer alignment.
+#[repr(C)]
+pub struct SsoStringN<const N: usize> {
+    repr: Repr<N>,
+}
+
+/// The default `SsoStringN`, the same size as `String`.
+pub type SsoString = SsoStringN<DEFAULT_INLINE_CAPACITY>;
+
 pub trait SsoStringable {
     fn to_sso_string(&self) -> SsoString;
 }
@@ -63,7 +166,7 @@ pub trait SsoStringable {
 impl SsoStrMetadata {
     #[inline]
     fn inline_len(&self) -> u8 {
-        self.data & 0b000_11111
+        self.data & 0b00_111111
     }
 
     #[inline]
@@ -71,65 +174,90 @@ impl SsoStrMetadata {
         (self.data & 0b010_00000) >> 6 == 1
     }
 
+    #[inline]
+    fn is_static_lifetime(&self) -> bool {
+        (self.data & 0b001_00000) >> 5 == 1
+    }
+
     #[inline]
     fn is_inlined(&self) -> bool {
         (self.data & 0b100_00000) >> 7 == 1
     }
 
     #[inline]
-    fn set_inline_len(&mut self, length: u8) {
-        self.data = self.data & 0b111_00000;
-        self.data = self.data | length;
+    const fn set_inline_len(&mut self, length: u8) {
+        self.data &= 0b11_000000;
+        self.data |= length;
     }
 
     #[inline]
     fn set_is_static(&mut self, flag: u8) {
-        self.data = self.data & 0b101_11111;
-        self.data = self.data | (flag << 6);
+        self.data &= 0b101_11111;
+        self.data |= flag << 6;
     }
 
     #[inline]
-    fn set_is_inlined(&mut self, flag: u8) {
-        self.data = self.data & 0b011_11111;
-        self.data = self.data | (flag << 7);
+    fn set_is_static_lifetime(&mut self, flag: u8) {
+        self.data &= 0b110_11111;
+        self.data |= flag << 5;
     }
 
     #[inline]
-    fn zero_all(&mut self) {
-        self.data = 0;
+    const fn set_is_inlined(&mut self, flag: u8) {
+        self.data &= 0b011_11111;
+        self.data |= flag << 7;
     }
 
     #[inline]
-    fn zero_flags(&mut self) {
-        self.data = self.data & 0b000_11111;
+    fn zero_all(&mut self) {
+        self.data = 0;
     }
 }
 
 
-impl SsoString {
+impl<const N: usize> SsoStringN<N> {
     const BIT_MASK_UPPER_U32_24: u32 = 0xFFFFFF00;
     const BIT_MASK_UPPER_U64_56: u64 = 0xFFFFFFFFFFFFFF00;
 
-    const INLINE_CAPACITY: usize = 23;
+    /// `N`, or the bytes the heap view already occupies if that's more.
+    const INLINE_CAPACITY: usize = if N > DEFAULT_INLINE_CAPACITY { N } else { DEFAULT_INLINE_CAPACITY };
+
+    /// The inline length has 6 bits of the metadata byte.
+    const VALID_INLINE_CAPACITY: () = assert!(N <= 63, "SsoStringN can't store more than 63 bytes inline");
 
+    /// The largest heap capacity the `<< 8` encoding can represent: `2^56 - 1` bytes on 64-bit
+    /// targets, `2^24 - 1` on 32-bit. Requests beyond it fail with `TryReserveError::CapacityOverflow`
+    /// (or panic, on the infallible paths) instead of truncating.
+    pub const MAX_CAPACITY: usize = usize::MAX >> 8;
+
+    /// An empty inline string. Usable in constants, e.g. `const EMPTY: SsoString = SsoString::new();`.
     #[inline]
-    pub fn new() -> Self {
-        Self::default()
+    pub const fn new() -> Self {
+        let mut string = Self::null_string();
+        string.metadata_mut().set_is_inlined(1);
+        string
     }
 
-    pub fn from(s: impl AsRef<str>) -> Self {
-        let s = s.as_ref();
+    /// An inline copy of `s`, usable in constants and statics:
+    /// `const KEYS: [SsoString; 2] = [SsoString::from_inline("get"), SsoString::from_inline("set")];`
+    ///
+    /// # Panics
+    /// Panics if `s` is longer than the inline capacity, which is a compile error in `const` context.
+    pub const fn from_inline(s: &str) -> Self {
+        assert!(s.len() <= Self::INLINE_CAPACITY, "string is too long to be stored inline");
+        let mut string = Self::new();
+        string.metadata_mut().set_inline_len(s.len() as u8);
+        unsafe { string.inline_ptr_mut().copy_from_nonoverlapping(s.as_ptr(), s.len()) };
+        string
+    }
+
+    /// Copies `s` into a new string, inlining it when it fits.
+    fn copy_from(s: &str) -> Self {
         if s.len() > Self::INLINE_CAPACITY {
-            let layout = unsafe { Layout::from_size_align_unchecked(s.len(), 4) };
-    
-            let string = SsoString { 
-                capacity: s.len() << 8, 
-                length: s.len(), 
-                pointer: unsafe { alloc::alloc(layout) } 
-            };
+            let string = Self::from_heap_parts(s.len() << 8, s.len(), Self::alloc_heap(s.len()));
 
             unsafe {
-                string.pointer.copy_from_nonoverlapping(s.as_ptr(), s.len());
+                string.heap().pointer.copy_from_nonoverlapping(s.as_ptr(), s.len());
             }
 
             return string;
@@ -150,25 +278,44 @@ impl SsoString {
 
     #[inline]
     pub fn from_static(s: &'static str) -> Self {
-        unsafe { Self::from_static_unchecked(s) }
+        let mut string = unsafe { Self::from_static_unchecked(s) };
+        string.metadata_mut().set_is_static_lifetime(1);
+        string
+    }
+
+    /// `from_static` for byte data, validating it as UTF-8 first.
+    pub fn from_static_bytes(bytes: &'static [u8]) -> Result<Self, str::Utf8Error> {
+        str::from_utf8(bytes).map(Self::from_static)
+    }
+
+    /// `from_static` for byte data, without validating it.
+    ///
+    /// # Safety
+    /// `bytes` must be valid UTF-8.
+    #[inline]
+    pub unsafe fn from_static_bytes_unchecked(bytes: &'static [u8]) -> Self {
+        Self::from_static(unsafe { str::from_utf8_unchecked(bytes) })
     }
 
+    /// Creates a copy-on-write string that borrows `s` without copying it.
+    ///
+    /// # Safety
+    /// `s` must outlive the returned string and every clone of it.
     #[inline]
     pub unsafe fn from_static_unchecked(s: &str) -> Self {
-        let mut string = SsoString { 
-            capacity: s.len() << 8, 
-            length: s.len(), 
-            pointer: s.as_ptr() as *mut u8,
-        };
+        debug_assert!(s.len() <= Self::MAX_CAPACITY, "capacity overflow");
+        let mut string = Self::from_heap_parts(s.len() << 8, s.len(), s.as_ptr() as *mut u8);
 
         let md = string.metadata_mut();
         md.set_is_static(1);
 
-        return string;
+        string
     }
 
+    /// Validates `bytes` as UTF-8. Short input is copied inline and the vector freed; longer input
+    /// keeps the vector's buffer and capacity without copying, unless the capacity exceeds `MAX_CAPACITY`.
     pub fn from_utf8(bytes: Vec<u8>) -> Result<Self, str::Utf8Error> {
-        match std::str::from_utf8(&bytes) {
+        match core::str::from_utf8(&bytes) {
             Ok(_) => {
                 let string = unsafe { Self::from_utf8_unchecked(bytes) };
                 Ok(string)
@@ -179,40 +326,131 @@ impl SsoString {
         }
     }
     
+    /// `from_utf8` with a vectorized validator: on x86_64 runs of ASCII are skipped 64 bytes at a time
+    /// with SSE2 and only the blocks around non-ASCII bytes go through the scalar validator. Other
+    /// targets use the scalar validator throughout. Errors are the same as `from_utf8`'s.
+    #[cfg(feature = "simd")]
+    pub fn from_utf8_simd(bytes: Vec<u8>) -> Result<Self, str::Utf8Error> {
+        #[cfg(target_arch = "x86_64")]
+        validate_utf8_sse2(&bytes)?;
+        #[cfg(not(target_arch = "x86_64"))]
+        core::str::from_utf8(&bytes)?;
+
+        Ok(unsafe { Self::from_utf8_unchecked(bytes) })
+    }
+    
+    /// Like `from_utf8`, without checking that `bytes` is valid UTF-8.
+    ///
+    /// # Safety
+    /// `bytes` must be valid UTF-8.
     #[inline]
     pub unsafe fn from_utf8_unchecked(bytes: Vec<u8>) -> Self {
-        let len = bytes.len();
-        let capacity = bytes.capacity();
+        if bytes.len() <= Self::INLINE_CAPACITY {
+            return Self::copy_from(unsafe { str::from_utf8_unchecked(&bytes) });
+        }
+
+        unsafe { Self::adopt_buffer(bytes, Self::MAX_CAPACITY) }
+    }
+
+    /// Takes over the buffer of `bytes`, or copies it if the buffer's capacity exceeds `max_capacity`
+    /// (the capacity field only holds `MAX_CAPACITY`, which is 16 MiB on 32-bit targets).
+    ///
+    /// Safety: `bytes` must be valid UTF-8 and `max_capacity` at most `MAX_CAPACITY`.
+    unsafe fn adopt_buffer(bytes: Vec<u8>, max_capacity: usize) -> Self {
+        if bytes.capacity() > max_capacity {
+            return Self::copy_from(unsafe { str::from_utf8_unchecked(&bytes) });
+        }
 
-        let mut md_bytes = mem::ManuallyDrop::new(bytes);
+        let mut bytes = mem::ManuallyDrop::new(bytes);
+        Self::from_heap_parts(bytes.capacity() << 8, bytes.len(), bytes.as_mut_ptr())
+    }
 
-        SsoString {
-            pointer: md_bytes.as_mut_ptr(),
-            length: len,
-            capacity: capacity << 8,
+    /// Decodes UTF-16 code units, failing on unpaired surrogates.
+    pub fn from_utf16(v: &[u16]) -> Result<Self, FromUtf16Error> {
+        let mut string = Self::new();
+        // Every code unit decodes to at least one byte
+        string.reserve(v.len());
+        for c in char::decode_utf16(v.iter().copied()) {
+            string.push(c.map_err(|_| FromUtf16Error(()))?);
         }
+        Ok(string)
     }
 
+    /// Decodes UTF-16 code units, replacing unpaired surrogates with `U+FFFD`.
+    pub fn from_utf16_lossy(v: &[u16]) -> Self {
+        char::decode_utf16(v.iter().copied())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    /// An empty string with room for `cap` bytes. Requests that fit inline don't allocate.
+    /// Panics with "capacity overflow" if `cap` exceeds `MAX_CAPACITY`.
     #[inline]
     pub fn with_capacity(cap: usize) -> Self {
-        let layout = unsafe {
-            Layout::from_size_align_unchecked(cap, 4)
-        };
+        if cap <= Self::INLINE_CAPACITY {
+            return Self::new();
+        }
+        Self::from_heap_parts(cap << 8, 0, Self::alloc_heap(cap))
+    }
+
+    /// Concatenates `pieces` with a single exact allocation: the iterator is walked once to sum the
+    /// lengths, then again to copy. Results that fit stay inline.
+    pub fn concat<I>(pieces: I) -> Self
+    where
+        I: IntoIterator,
+        I::IntoIter: Clone,
+        I::Item: AsRef<str>,
+    {
+        Self::join(pieces, "")
+    }
+
+    /// Like `concat`, with `sep` between pieces, mirroring `[&str]::join`.
+    pub fn join<I>(pieces: I, sep: &str) -> Self
+    where
+        I: IntoIterator,
+        I::IntoIter: Clone,
+        I::Item: AsRef<str>,
+    {
+        let pieces = pieces.into_iter();
+        let (count, total) = pieces.clone().fold((0usize, 0usize), |(count, total), piece| {
+            (count + 1, total.saturating_add(piece.as_ref().len()))
+        });
+        let total = total.saturating_add(sep.len().saturating_mul(count.saturating_sub(1)));
+
+        let mut string = Self::new();
+        string.reserve_exact(total);
+        for (i, piece) in pieces.enumerate() {
+            if i > 0 {
+                string.push_str(sep);
+            }
+            string.push_str(piece.as_ref());
+        }
+        string
+    }
 
-        Self {
-            length: 0,
-            capacity: cap << 8,
-            pointer: unsafe { alloc::alloc(layout) }
+    /// Collects chars when the encoded length is known up front, allocating exactly `byte_len` bytes once
+    /// instead of growing as `collect` does. The hint only affects allocation; if it's wrong the string
+    /// grows or keeps the spare capacity as usual. Results that fit stay inline.
+    pub fn from_char_iter_exact<I: IntoIterator<Item = char>>(iter: I, byte_len: usize) -> Self {
+        let mut string = Self::new();
+        string.reserve_exact(byte_len);
+        for c in iter {
+            string.push(c);
         }
+        string
     }
 
+    /// Length in bytes, not chars. Use `char_count` for the number of chars.
     #[inline]
     pub fn len(&self) -> usize {
-        if self.is_inlined() {
-            self.metadata().inline_len() as usize
-        } else {
-            self.length
-        }
+        self.data_parts().1
+    }
+
+    /// Number of chars (Unicode scalar values), as opposed to `len()`, which counts bytes.
+    /// This walks the whole string.
+    #[inline]
+    pub fn char_count(&self) -> usize {
+        self.chars().count()
     }
 
     #[inline]
@@ -220,7 +458,88 @@ impl SsoString {
         if self.is_inlined() {
             return Self::INLINE_CAPACITY;
         }
-        self.capacity >> 8
+        self.heap().capacity >> 8
+    }
+
+    /// The capacity an empty string ends up with after appending `len` bytes in one go: the inline
+    /// capacity if `len` fits inline, otherwise the first heap capacity the growth policy picks.
+    /// Useful to pre-size strings with `with_capacity` exactly as the crate would.
+    #[inline]
+    pub fn capacity_for(len: usize) -> usize {
+        if len <= Self::INLINE_CAPACITY {
+            Self::INLINE_CAPACITY
+        } else {
+            Self::grow_capacity(Self::INLINE_CAPACITY, len)
+        }
+    }
+
+    /// The original `&'static str` of a string made with `from_static`, without copying. `None` once
+    /// the string has been copied by a write, for inline and heap strings, and for `from_static_unchecked`,
+    /// whose data isn't known to be `'static`.
+    pub fn as_static(&self) -> Option<&'static str> {
+        let md = self.metadata();
+        if md.is_static() && md.is_static_lifetime() {
+            Some(unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.heap().pointer, self.heap().length)) })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn representation(&self) -> Representation {
+        let md = self.metadata();
+        if md.is_inlined() {
+            Representation::Inline
+        } else if md.is_static() {
+            Representation::Static
+        } else {
+            Representation::Heap
+        }
+    }
+
+    /// Number of bytes that can be pushed without reallocating. Always 0 for static strings,
+    /// since they must be copied before any write.
+    #[inline]
+    pub fn capacity_remaining(&self) -> usize {
+        if self.is_static() {
+            return 0;
+        }
+        self.capacity() - self.len()
+    }
+
+    /// Capacity of the owned heap buffer, or `None` for inline and static strings.
+    #[inline]
+    pub fn heap_capacity(&self) -> Option<usize> {
+        self.is_heap_allocated().then_some(self.heap().capacity >> 8)
+    }
+
+    /// Bytes this string owns on the heap: its heap capacity, or 0 for inline and static strings.
+    #[inline]
+    pub fn allocation_size(&self) -> usize {
+        self.heap_capacity().unwrap_or(0)
+    }
+
+    /// The uninitialized bytes between `len()` and `capacity()`, to be filled in place and then
+    /// committed with `set_len`. For inline strings this is the rest of the inline buffer.
+    /// Static strings are copied first.
+    pub fn spare_capacity_mut(&mut self) -> &mut [mem::MaybeUninit<u8>] {
+        self.ensure_owned();
+        let len = self.len();
+        let spare = self.capacity() - len;
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr().add(len) as *mut mem::MaybeUninit<u8>, spare) }
+    }
+
+    /// Sets the length of the string in bytes.
+    ///
+    /// # Safety
+    /// - `new_len` must be at most `capacity()`.
+    /// - The first `new_len` bytes must be initialized and valid UTF-8, e.g. written through
+    ///   `spare_capacity_mut`. The string is never revalidated.
+    /// - If `new_len > len()`, the string must not be static; call `spare_capacity_mut` first.
+    #[inline]
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.capacity());
+        self.update_len(new_len);
     }
 
     #[inline]
@@ -239,41 +558,43 @@ impl SsoString {
         let s_ptr = s.as_ptr();
         
         // Cache metadata access - this is expensive due to pointer casting
-        let md_data = unsafe { (self as *mut SsoString as *mut u8).read() };
+        let md_data = unsafe { (self as *mut Self as *mut u8).read() };
         let is_inlined = (md_data & 0b100_00000) != 0;
         
         if is_inlined {
-            let curr_len = (md_data & 0b000_11111) as usize;
+            let curr_len = (md_data & 0b00_111111) as usize;
             let new_len = curr_len + s_len;
             
             if new_len <= Self::INLINE_CAPACITY {
                 // Fast path: inline to inline
-                let dst = unsafe { (self as *mut SsoString as *mut u8).add(1).add(curr_len) };
+                let dst = unsafe { (self as *mut Self as *mut u8).add(1).add(curr_len) };
                 unsafe { dst.copy_from_nonoverlapping(s_ptr, s_len) };
-                unsafe { (self as *mut SsoString as *mut u8).write(0b100_00000 | new_len as u8) };
+                unsafe { (self as *mut Self as *mut u8).write(0b100_00000 | new_len as u8) };
                 return;
             }
             
             // Inline to heap transition
-            let new_cap = (new_len * 3) >> 1; // Faster than division
-            let layout = unsafe { Layout::from_size_align_unchecked(new_cap, 4) };
-            let new_ptr = unsafe { alloc::alloc(layout) };
+            let new_cap = Self::grow_capacity(Self::INLINE_CAPACITY, new_len);
+            let new_ptr = Self::alloc_heap(new_cap);
             
             // Copy existing inline data
-            let src = unsafe { (self as *const SsoString as *const u8).add(1) };
+            let src = unsafe { (self as *const Self as *const u8).add(1) };
             unsafe { new_ptr.copy_from_nonoverlapping(src, curr_len) };
             // Append new data
             unsafe { new_ptr.add(curr_len).copy_from_nonoverlapping(s_ptr, s_len) };
             
-            self.capacity = new_cap << 8;
-            self.length = new_len;
-            self.pointer = new_ptr;
+            self.heap_mut().capacity = new_cap << 8;
+            self.heap_mut().length = new_len;
+            self.heap_mut().pointer = new_ptr;
+            self.debug_validate();
             return;
         }
         
         // Heap-allocated path
         let is_static = (md_data & 0b010_00000) != 0;
-        let curr_len = self.length;
+        let curr_len = self.heap().length;
+        // Both lengths are at most `isize::MAX`, so this can't wrap; lengths past `MAX_CAPACITY`
+        // are rejected when the grown buffer is allocated
         let new_len = curr_len + s_len;
         
         if is_static {
@@ -281,49 +602,51 @@ impl SsoString {
             
             if fits_inline {
                 // Static to inline
-                let dst = unsafe { (self as *mut SsoString as *mut u8).add(1) };
-                unsafe { dst.copy_from_nonoverlapping(self.pointer, curr_len) };
+                let dst = unsafe { (self as *mut Self as *mut u8).add(1) };
+                unsafe { dst.copy_from_nonoverlapping(self.heap().pointer, curr_len) };
                 unsafe { dst.add(curr_len).copy_from_nonoverlapping(s_ptr, s_len) };
-                unsafe { (self as *mut SsoString as *mut u8).write(0b100_00000 | new_len as u8) };
+                unsafe { (self as *mut Self as *mut u8).write(0b100_00000 | new_len as u8) };
+                self.debug_validate();
                 return;
             }
             
             // Static to heap
-            let new_cap = (new_len * 3) >> 1;
-            let layout = unsafe { Layout::from_size_align_unchecked(new_cap, 4) };
-            let new_ptr = unsafe { alloc::alloc(layout) };
+            let new_cap = Self::grow_capacity(curr_len, new_len);
+            let new_ptr = Self::alloc_heap(new_cap);
             
-            unsafe { new_ptr.copy_from_nonoverlapping(self.pointer, curr_len) };
+            unsafe { new_ptr.copy_from_nonoverlapping(self.heap().pointer, curr_len) };
             unsafe { new_ptr.add(curr_len).copy_from_nonoverlapping(s_ptr, s_len) };
             
-            self.capacity = new_cap << 8;
-            self.length = new_len;
-            self.pointer = new_ptr;
+            self.heap_mut().capacity = new_cap << 8;
+            self.heap_mut().length = new_len;
+            self.heap_mut().pointer = new_ptr;
+            self.debug_validate();
             return;
         }
         
         // Mutable heap path
-        let curr_cap = self.capacity >> 8;
+        let curr_cap = self.heap().capacity >> 8;
         
         if new_len > curr_cap {
             // Need reallocation
-            let new_cap = (new_len * 3) >> 1;
-            let new_layout = unsafe { Layout::from_size_align_unchecked(new_cap, 4) };
-            
-            unsafe {
-                self.pointer = alloc::realloc(self.pointer, new_layout, new_cap);
-            }
-            
-            self.capacity = new_cap << 8;
+            let new_cap = Self::grow_capacity(curr_cap, new_len);
+            unsafe { self.resize_heap(new_cap) };
         }
         
         // Append new data
-        unsafe { self.pointer.add(curr_len).copy_from_nonoverlapping(s_ptr, s_len) };
-        self.length = new_len;
+        unsafe { self.heap().pointer.add(curr_len).copy_from_nonoverlapping(s_ptr, s_len) };
+        self.heap_mut().length = new_len;
+        self.debug_validate();
     }
 
     /// Assumption is about the current state of the string, not the state after the operation
     /// Ideally, the value passed to `assumption` should be a literal or const value to allow for constant folding
+    ///
+    /// # Safety
+    /// The string must currently be in the state described by `PRECOND`. The `*AssumeCapacity`
+    /// variants additionally require that `s` fits in the remaining capacity. `Ascii` makes no
+    /// assumption about the representation, but requires both the current contents and `s` to be ASCII.
+    /// It is equivalent to `push_str`: appending a `&str` never needs char boundary checks anyway.
     pub unsafe fn push_str_assume<const PRECOND: SsosPrecondType>(&mut self, s: &str) {
         let s_len = s.len();
         let s_ptr = s.as_ptr();
@@ -335,11 +658,11 @@ impl SsoString {
                 let length = md.inline_len() as usize;
                 let new_length = length + s_len;
                 if new_length > Self::INLINE_CAPACITY {
-                    self.force_heap_relocation((new_length * 3) >> 1);
+                    self.force_heap_relocation(Self::grow_capacity(Self::INLINE_CAPACITY, new_length));
                     unsafe {
-                        let ptr = self.pointer.add(length);
+                        let ptr = self.heap().pointer.add(length);
                         ptr.copy_from_nonoverlapping(s_ptr, s_len);
-                        self.length = new_length;
+                        self.heap_mut().length = new_length;
                     }
                 }
                 else {
@@ -361,66 +684,260 @@ impl SsoString {
                 }
             }
             SsosPrecond::Static => {
-                let new_length = self.length + s_len;
-                let new_capacity = (new_length * 3) >> 1;
+                let new_length = self.heap().length + s_len;
+                let new_capacity = Self::grow_capacity(self.heap().length, new_length);
 
                 unsafe {
-                    let layout = Layout::from_size_align_unchecked(new_capacity, 4);
-                    let ptr = alloc::alloc(layout);
-                    ptr.copy_from_nonoverlapping(self.pointer, self.length);
-                    ptr.add(self.length).copy_from_nonoverlapping(s_ptr, s_len);
-                    self.pointer = ptr;
+                    let ptr = Self::alloc_heap(new_capacity);
+                    ptr.copy_from_nonoverlapping(self.heap().pointer, self.heap().length);
+                    ptr.add(self.heap().length).copy_from_nonoverlapping(s_ptr, s_len);
+                    self.heap_mut().pointer = ptr;
                 }
-                self.capacity = new_capacity << 8;
-                self.length = new_length;
+                self.heap_mut().capacity = new_capacity << 8;
+                self.heap_mut().length = new_length;
             }
             SsosPrecond::Heap => {
-                let new_length = self.length + s_len;
-                let capacity = self.capacity >> 8;
+                let new_length = self.heap().length + s_len;
+                let capacity = self.heap().capacity >> 8;
                 if new_length > capacity {
-                    let new_capacity = (capacity * 3) >> 1;
-                    unsafe {
-                        let layout = Layout::from_size_align_unchecked(new_capacity, 4);
-                        self.pointer = alloc::realloc(self.pointer, layout, new_capacity);
-                    }
-                    self.capacity = new_capacity << 8;
+                    let new_capacity = Self::grow_capacity(capacity, new_length);
+                    unsafe { self.resize_heap(new_capacity) };
                 }
                 unsafe {
-                    self.pointer
-                        .add(self.length)
+                    self.heap().pointer
+                        .add(self.heap().length)
                         .copy_from_nonoverlapping(s_ptr, s_len);
                 }
-                self.length += s_len;
+                self.heap_mut().length += s_len;
             }
             SsosPrecond::HeapAssumeCapacity => {
                 unsafe {
-                    self.pointer
-                        .add(self.length)
+                    self.heap().pointer
+                        .add(self.heap().length)
                         .copy_from_nonoverlapping(s_ptr, s_len);
                 }
-                self.length += s_len;
+                self.heap_mut().length += s_len;
+            }
+            SsosPrecond::Ascii => {
+                debug_assert!(s.is_ascii(), "push_str_assume::<Ascii> called with non-ASCII input");
+                self.push_str(s);
             }
-            _ => panic!("{:?} assumption isn't relevent in this context", assumption),
         }
     }
     
+    /// Appends `bytes` like `push_str`, without validating them, for input already checked upstream.
+    ///
+    /// # Safety
+    /// `bytes` must be valid UTF-8 on its own; a char must not be split across calls.
+    #[inline]
+    pub unsafe fn push_bytes_unchecked(&mut self, bytes: &[u8]) {
+        self.push_str(unsafe { str::from_utf8_unchecked(bytes) });
+    }
+
+    /// Like `push_str`, but returns an error instead of panicking if the new length doesn't fit the
+    /// capacity field (`MAX_CAPACITY`) or the allocation fails.
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), TryReserveError> {
+        self.try_reserve(s.len())?;
+        self.push_str(s);
+        Ok(())
+    }
+
+    /// Appends every part in order after reserving room for all of them at once, so the batch grows
+    /// the buffer (or moves it from inline to the heap) at most once.
+    pub fn push_str_many(&mut self, parts: &[&str]) {
+        let total = parts.iter().fold(0usize, |total, part| total.saturating_add(part.len()));
+        self.reserve(total);
+        for part in parts {
+            self.push_str(part);
+        }
+    }
+
+    /// Appends `fill` until the string is at least `target_len` bytes long. Capacity is reserved once
+    /// up front. With a multi-byte `fill` the result may overshoot `target_len` by less than one char.
+    pub fn pad_to(&mut self, target_len: usize, fill: char) {
+        let missing = target_len.saturating_sub(self.len());
+        self.push_fill(missing.div_ceil(fill.len_utf8()), fill);
+    }
+
+    /// Like `pad_to`, but pads to `target_chars` chars rather than bytes.
+    pub fn pad_to_chars(&mut self, target_chars: usize, fill: char) {
+        let missing = target_chars.saturating_sub(self.chars().count());
+        self.push_fill(missing, fill);
+    }
+
+    /// Appends `other`, consuming it. If `self` is inline and `other` is a heap string with room for
+    /// `self`'s bytes in front, `other`'s buffer is reused instead of copying it.
+    pub fn append(&mut self, mut other: Self) {
+        let len = self.len();
+        let can_steal = other.is_heap_allocated() && other.capacity() != 0 && other.capacity() - other.len() >= len;
+        if self.is_inlined() && can_steal {
+            unsafe {
+                other.heap().pointer.add(len).copy_from(other.heap().pointer, other.heap().length);
+                other.heap().pointer.copy_from_nonoverlapping(self.inline_ptr(), len);
+            }
+            other.heap_mut().length += len;
+            *self = other;
+            return;
+        }
+        self.push_str(&other);
+    }
+
     pub fn push(&mut self, c: char) {
         let mut buf = [0u8; 4];
         let s_char = c.encode_utf8(&mut buf);
         self.push_str(s_char);
     }
 
+    /// Ensures there is room for at least `additional` more bytes, over-allocating to amortize repeated growth.
+    /// Does nothing if the current capacity is already sufficient, so an inline string stays inline while it fits.
     pub fn reserve(&mut self, additional: usize) {
+        if self.capacity_remaining() >= additional {
+            return;
+        }
+
+        if let Err(e) = self.try_reserve(additional) {
+            e.handle();
+        }
+    }
+
+    /// Fallible version of `reserve`: reports capacity overflow or allocator failure instead of aborting.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        if self.capacity_remaining() >= additional {
+            return Ok(());
+        }
+
+        let len = self.len();
         let curr_capacity = self.capacity();
-        let new_capacity = curr_capacity + additional;
-        let reallocated = self.force_heap_relocation(new_capacity);
-        if !reallocated {
-            let layout = unsafe { Layout::from_size_align_unchecked(new_capacity, 4) };
-               
-            unsafe {
-                self.pointer = alloc::realloc(self.pointer, layout, new_capacity);
+        let needed = len.checked_add(additional).ok_or(TryReserveError::CapacityOverflow)?;
+        self.try_grow_to(Self::grow_capacity(curr_capacity, needed))
+    }
+
+    /// Like `reserve`, but grows to exactly `len() + additional` bytes without any growth slack.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        if self.capacity_remaining() >= additional {
+            return;
+        }
+
+        let grown = self.len().checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)
+            .and_then(|new_capacity| self.try_grow_to(new_capacity));
+        if let Err(e) = grown {
+            e.handle();
+        }
+    }
+
+    /// Moves a heap string whose contents now fit inline back into inline storage and frees its buffer.
+    /// Inline and static strings are left alone.
+    pub fn shrink_or_inline(&mut self) {
+        if self.is_heap_allocated() && self.len() <= Self::INLINE_CAPACITY {
+            *self = Self::copy_from(self.as_str());
+        }
+    }
+
+    /// Removes the byte range `range` and returns its chars as an iterator. The gap is closed when the
+    /// iterator is dropped, even if it wasn't fully consumed. Panics if the range is out of bounds or
+    /// either endpoint isn't a char boundary. Static strings are copied first.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, N> {
+        let (start, end) = self.char_range(range);
+        self.ensure_owned();
+
+        let string = self as *mut Self;
+        // The drained bytes stay in place until Drain's destructor shifts the tail over them
+        let drained = unsafe {
+            let ptr = (*string).as_mut_ptr().add(start);
+            str::from_utf8_unchecked(slice::from_raw_parts(ptr, end - start))
+        };
+
+        Drain { string, start, end, iter: drained.chars() }
+    }
+
+    /// Replaces the byte range `range` with `replace_with`, growing or shrinking the string as needed.
+    /// Panics if the range is out of bounds or either endpoint isn't a char boundary.
+    pub fn replace_range<R: RangeBounds<usize>>(&mut self, range: R, replace_with: &str) {
+        let (start, end) = self.char_range(range);
+        let len = self.len();
+        let new_len = len - (end - start) + replace_with.len();
+
+        self.ensure_owned();
+        if new_len > len {
+            self.reserve(new_len - len);
+        }
+
+        unsafe {
+            let ptr = self.as_mut_ptr();
+            ptr.add(start + replace_with.len()).copy_from(ptr.add(end), len - end);
+            ptr.add(start).copy_from_nonoverlapping(replace_with.as_ptr(), replace_with.len());
+        }
+        self.update_len(new_len);
+    }
+
+    /// Inserts `ch` before the `char_idx`th char, or at the end if `char_idx` equals the char count.
+    /// Panics if `char_idx` is greater than the char count.
+    pub fn insert_at_char(&mut self, char_idx: usize, ch: char) {
+        let offset = match self.char_indices().nth(char_idx) {
+            Some((offset, _)) => offset,
+            None => {
+                let char_count = self.chars().count();
+                assert!(char_idx == char_count, "char index {} is out of bounds for {} chars", char_idx, char_count);
+                self.len()
             }
-            self.set_capacity(new_capacity);
+        };
+        self.replace_range(offset..offset, ch.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Removes and returns the `char_idx`th char. Panics if `char_idx` is not less than the char count.
+    pub fn remove_at_char(&mut self, char_idx: usize) -> char {
+        let Some((offset, ch)) = self.char_indices().nth(char_idx) else {
+            panic!("char index {} is out of bounds for {} chars", char_idx, self.chars().count());
+        };
+        self.replace_range(offset..offset + ch.len_utf8(), "");
+        ch
+    }
+
+    /// Appends a copy of the byte range `src` of this string to its end. Panics if the range is out
+    /// of bounds or either endpoint isn't a char boundary.
+    pub fn extend_from_within<R: RangeBounds<usize>>(&mut self, src: R) {
+        let (start, end) = self.char_range(src);
+        let len = self.len();
+        let count = end - start;
+
+        self.ensure_owned();
+        self.reserve(count);
+
+        unsafe {
+            let ptr = self.as_mut_ptr();
+            ptr.add(len).copy_from_nonoverlapping(ptr.add(start), count);
+        }
+        self.update_len(len + count);
+    }
+
+    /// Shortens the string to `new_len` bytes. Has no effect if `new_len` is greater than the current length.
+    /// Panics if `new_len` does not lie on a char boundary.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len <= self.len() {
+            assert!(self.as_str().is_char_boundary(new_len), "new_len does not lie on a char boundary");
+            self.update_len(new_len);
+        }
+    }
+
+    /// Like `truncate`, but rounds `max_len` down to the nearest char boundary instead of panicking.
+    pub fn truncate_lossy(&mut self, max_len: usize) {
+        if max_len >= self.len() {
+            return;
+        }
+        let s = self.as_str();
+        let mut new_len = max_len;
+        while !s.is_char_boundary(new_len) {
+            new_len -= 1;
+        }
+        self.update_len(new_len);
+    }
+
+    /// Shortens the string to its first `max_chars` chars. Has no effect if it has no more than that.
+    /// Static strings are shortened without copying, since the prefix is still the same static data.
+    pub fn truncate_chars(&mut self, max_chars: usize) {
+        if let Some((new_len, _)) = self.char_indices().nth(max_chars) {
+            self.update_len(new_len);
         }
     }
 
@@ -432,6 +949,37 @@ impl SsoString {
         self.as_str().split_ascii_whitespace()
     }
 
+    /// Splits on Unicode whitespace into owned tokens. Tokens that fit are inlined, so short ones
+    /// don't allocate.
+    pub fn split_whitespace_owned(&self) -> Vec<Self> {
+        self.as_str().split_whitespace().map(Self::from).collect()
+    }
+
+    /// Like `split`, but returns owned pieces, see `split_whitespace_owned`.
+    pub fn split_owned(&self, pat: &str) -> Vec<Self> {
+        self.as_str().split(pat).map(Self::from).collect()
+    }
+
+    /// Like `split_terminator`, but returns owned pieces, see `split_whitespace_owned`.
+    pub fn split_terminator_owned(&self, pat: &str) -> Vec<Self> {
+        self.as_str().split_terminator(pat).map(Self::from).collect()
+    }
+
+    /// Like `rsplit`, but returns owned pieces, see `split_whitespace_owned`.
+    pub fn rsplit_owned(&self, pat: &str) -> Vec<Self> {
+        self.as_str().rsplit(pat).map(Self::from).collect()
+    }
+
+    /// Like `splitn`, but returns owned pieces, see `split_whitespace_owned`.
+    pub fn splitn_owned(&self, n: usize, pat: &str) -> Vec<Self> {
+        self.as_str().splitn(n, pat).map(Self::from).collect()
+    }
+
+    /// Like `rsplitn`, but returns owned pieces, see `split_whitespace_owned`.
+    pub fn rsplitn_owned(&self, n: usize, pat: &str) -> Vec<Self> {
+        self.as_str().rsplitn(n, pat).map(Self::from).collect()
+    }
+
     pub fn split_once(&self, delimiter: &str) -> Option<(&str, &str)> {
         self.as_str().split_once(delimiter)
     }
@@ -456,312 +1004,1582 @@ impl SsoString {
         self.as_str().char_indices()
     }
 
-    pub fn contains(&self, pat: &str) -> bool {
-        self.as_str().contains(pat)
+    #[inline]
+    pub fn first_char(&self) -> Option<char> {
+        self.chars().next()
     }
 
-    pub fn starts_with(&self, pat: &str) -> bool {
-        self.as_str().starts_with(pat)
+    #[inline]
+    pub fn last_char(&self) -> Option<char> {
+        self.chars().next_back()
     }
 
-    pub fn ends_with(&self, pat: &str) -> bool {
-        self.as_str().ends_with(pat)
+    #[inline]
+    pub fn chars_rev(&self) -> core::iter::Rev<str::Chars<'_>> {
+        self.chars().rev()
     }
 
+    /// The `n`th char (not byte), counting from 0. Walks the string, so this is O(n).
     #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    pub fn nth_char(&self, n: usize) -> Option<char> {
+        self.chars().nth(n)
     }
 
+    /// Like `char_indices`, but starts at `byte_offset`. Indices are still relative to the start of
+    /// the string. Panics if `byte_offset` is out of bounds or not a char boundary.
+    pub fn char_indices_from(&self, byte_offset: usize) -> impl DoubleEndedIterator<Item = (usize, char)> + '_ {
+        self.as_str()[byte_offset..].char_indices().map(move |(i, c)| (i + byte_offset, c))
+    }
+
+    /// Returns true if `index` is the start or end of the string or the first byte of a char.
     #[inline]
-    pub fn as_ptr(&self) -> *const u8 {
-        if self.is_inlined() {
-            self.inline_ptr()
-        } else {
-            self.pointer
+    pub fn is_char_boundary(&self, index: usize) -> bool {
+        let bytes = self.as_bytes();
+        match bytes.get(index) {
+            // Continuation bytes are 0b10xx_xxxx
+            Some(&b) => (b as i8) >= -0x40,
+            None => index == bytes.len(),
         }
     }
 
-    #[inline]
-    pub fn as_mut_ptr(&mut self) -> *mut u8 {
-        if self.is_inlined() {
-            self.inline_ptr() as *mut u8
-        } else {
-            self.pointer
+    /// The largest char boundary `<= index`, clamped to `len()`.
+    pub fn floor_char_boundary(&self, index: usize) -> usize {
+        if index >= self.len() {
+            return self.len();
+        }
+        // A char is at most 4 bytes, so this loops at most 3 times
+        let mut i = index;
+        while !self.is_char_boundary(i) {
+            i -= 1;
         }
+        i
     }
 
-    #[inline]
-    pub fn as_bytes<'a>(&'a self) -> &'a [u8] {
-        let ptr = self.as_ptr();
-        let length = self.len();
-        let bytes = unsafe { slice::from_raw_parts(ptr, length) };
-        bytes
+    /// The smallest char boundary `>= index`, clamped to `len()`.
+    pub fn ceil_char_boundary(&self, index: usize) -> usize {
+        if index >= self.len() {
+            return self.len();
+        }
+        let mut i = index;
+        while !self.is_char_boundary(i) {
+            i += 1;
+        }
+        i
     }
 
-    #[inline]
-    pub fn as_bytes_mut<'a>(&'a mut self) -> &'a mut [u8]{
-        let ptr = self.as_mut_ptr();
-        let length = self.len();
-        let bytes = unsafe { slice::from_raw_parts_mut(ptr, length) };
-        bytes
+    pub fn contains(&self, pat: &str) -> bool {
+        self.as_str().contains(pat)
     }
 
-    #[inline]
-    pub fn as_str<'a>(&'a self) -> &'a str {
-        let md = self.metadata().data;
-        unsafe  {
-            if md >> 7 == 1{
-                std::str::from_utf8_unchecked(
-                    std::slice::from_raw_parts(self.inline_ptr(), (md & 0b000_11111) as usize)
-                )
-            }
-            else {
-                std::str::from_utf8_unchecked(
-                    std::slice::from_raw_parts(self.pointer, self.length)
-                )
-            }
-        }
+    pub fn starts_with(&self, pat: &str) -> bool {
+        self.as_str().starts_with(pat)
+    }
+
+    pub fn ends_with(&self, pat: &str) -> bool {
+        self.as_str().ends_with(pat)
     }
 
+    /// Compares the contents with `other`. The same comparison `==` uses, for call sites (or generic
+    /// code) that only have a `&str` to hand.
     #[inline]
-    pub unsafe fn as_str_assume<'a, const PRECOND: SsosPrecondType>(&'a self) -> &'a str {
-        let assumption= SsosPrecond::from(PRECOND);
-        match assumption {
-            SsosPrecond::Heap |
-            SsosPrecond::HeapAssumeCapacity |
-            SsosPrecond::Static => {
-                unsafe {
-                    std::str::from_utf8_unchecked(std::slice::from_raw_parts(self.pointer, self.length))
-                }
-            }
-            SsosPrecond::Inline |
-            SsosPrecond::InlineAssumeCapacity => {
-                unsafe {
-                    let md = (self as *const SsoString as *const u8).read();
-                    let ptr = (self as *const SsoString as *const u8).add(1);
-                    let len = (md & 0b000_11111) as usize;
-                    std::str::from_utf8_unchecked(std::slice::from_raw_parts(ptr, len))
-                }
-            }
-            _ => panic!("{:?} assumption isn't relevent in this context", assumption),
+    pub fn eq_str(&self, other: &str) -> bool {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            bytes_eq_sse2(self.as_bytes(), other.as_bytes())
+        }
+        #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+        {
+            self.as_str() == other
         }
     }
 
-    pub fn to_string(&self) -> String {
-        self.as_str().to_string()
+    /// `starts_with`, ignoring ASCII case. Doesn't allocate.
+    pub fn starts_with_ignore_ascii_case(&self, prefix: &str) -> bool {
+        let bytes = self.as_bytes();
+        bytes.len() >= prefix.len() && bytes[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
     }
 
-    #[inline]
-    fn metadata(&self) -> &SsoStrMetadata {
-        let metadata = self as *const SsoString as *const SsoStrMetadata;
-        unsafe { metadata.as_ref().unwrap_unchecked() }
+    /// `ends_with`, ignoring ASCII case. Doesn't allocate.
+    pub fn ends_with_ignore_ascii_case(&self, suffix: &str) -> bool {
+        let bytes = self.as_bytes();
+        bytes.len() >= suffix.len() && bytes[bytes.len() - suffix.len()..].eq_ignore_ascii_case(suffix.as_bytes())
     }
 
+    /// Byte-wise comparison with ASCII letters folded to lowercase, stopping at the first difference.
+    /// Consistent with `eq_ignore_ascii_case`; non-ASCII bytes compare as-is. Doesn't allocate.
+    pub fn cmp_ignore_ascii_case(&self, other: &str) -> core::cmp::Ordering {
+        let lhs = self.as_bytes().iter().map(u8::to_ascii_lowercase);
+        let rhs = other.as_bytes().iter().map(u8::to_ascii_lowercase);
+        lhs.cmp(rhs)
+    }
 
+    /// Byte index of the first occurrence of `b`. Scans a word at a time, so it's much faster than
+    /// `find` on long strings.
     #[inline]
-    fn metadata_mut(&mut self) -> &mut SsoStrMetadata {
-        let metadata = self as *mut SsoString as *mut SsoStrMetadata;
-        unsafe { metadata.as_mut().unwrap_unchecked() }
+    pub fn find_byte(&self, b: u8) -> Option<usize> {
+        memchr(b, self.as_bytes())
     }
 
+    /// Byte index of the last occurrence of `b`, see `find_byte`.
     #[inline]
-    fn inline_ptr(&self) -> *const u8 {
-        let ptr = self as *const SsoString as *const u8;
-        unsafe { ptr.add(1) }
+    pub fn rfind_byte(&self, b: u8) -> Option<usize> {
+        memrchr(b, self.as_bytes())
     }
 
+    /// Whether `c` occurs in the string. ASCII chars never appear inside a multi-byte sequence, so
+    /// they are searched for as bytes with `find_byte`; other chars fall back to `str::contains`.
     #[inline]
-    fn inline_ptr_mut(&mut self) -> *mut u8 {
-        let ptr = self as *mut SsoString as *mut u8;
-        unsafe { ptr.add(1) }
+    pub fn contains_char(&self, c: char) -> bool {
+        if c.is_ascii() {
+            self.find_byte(c as u8).is_some()
+        } else {
+            self.as_str().contains(c)
+        }
+    }
+
+    /// Number of occurrences of `c`. ASCII chars are counted as bytes, which the compiler
+    /// vectorizes; other chars fall back to `str::matches`.
+    pub fn count_char(&self, c: char) -> usize {
+        if c.is_ascii() {
+            let b = c as u8;
+            self.as_bytes().iter().filter(|&&x| x == b).count()
+        } else {
+            self.as_str().matches(c).count()
+        }
+    }
+
+    /// Number of non-overlapping occurrences of `pat`, as found by `str::matches`.
+    pub fn matches_count(&self, pat: &str) -> usize {
+        self.as_str().matches(pat).count()
+    }
+
+    /// Byte ranges of the non-overlapping occurrences of `pat`, as found by `str::match_indices`.
+    pub fn match_ranges(&self, pat: &str) -> Vec<core::ops::Range<usize>> {
+        self.as_str().match_indices(pat).map(|(i, m)| i..i + m.len()).collect()
+    }
+
+    /// Owned copy of `trim()`. A trimmed static string still points at the same static data.
+    pub fn trimmed(&self) -> Self {
+        self.substring(self.as_str().trim())
+    }
+
+    /// Owned copy of `trim_start()`, see `trimmed`.
+    pub fn trimmed_start(&self) -> Self {
+        self.substring(self.as_str().trim_start())
+    }
+
+    /// Owned copy of `trim_end()`, see `trimmed`.
+    pub fn trimmed_end(&self) -> Self {
+        self.substring(self.as_str().trim_end())
+    }
+
+    /// Copy with leading and trailing whitespace removed and every inner run of whitespace collapsed
+    /// into a single space. Short results stay inline.
+    pub fn normalize_whitespace(&self) -> Self {
+        let mut string = Self::new();
+        for word in self.as_str().split_whitespace() {
+            if !string.is_empty() {
+                string.push(' ');
+            }
+            string.push_str(word);
+        }
+        string
+    }
+
+    /// Returns true if every char is Unicode whitespace. The empty string counts as whitespace,
+    /// matching `str::trim().is_empty()`.
+    pub fn is_whitespace(&self) -> bool {
+        self.chars().all(char::is_whitespace)
+    }
+
+    /// Splits the string against `base` into the length of their shared prefix and the remaining suffix,
+    /// so it can be stored as `(shared_len, suffix)`. The shared length always lies on a char boundary.
+    pub fn prefix_compress(&self, base: &str) -> (usize, &str) {
+        let s = self.as_str();
+        let mut shared = s.bytes()
+            .zip(base.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !s.is_char_boundary(shared) {
+            shared -= 1;
+        }
+        (shared, &s[shared..])
+    }
+
+    /// Rebuilds a string produced by `prefix_compress` from its base, shared length and suffix.
+    pub fn prefix_expand(base: &str, shared_len: usize, suffix: &str) -> Self {
+        debug_assert!(base.is_char_boundary(shared_len), "shared_len must lie on a char boundary in base");
+        let prefix = &base[..shared_len];
+        let total = prefix.len() + suffix.len();
+
+        let mut string = Self::with_capacity(total);
+        string.push_str(prefix);
+        string.push_str(suffix);
+        string
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.data_parts().0
+    }
+
+    /// Static strings are copied first, so the returned pointer never points into read-only memory.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ensure_owned();
+        if self.is_inlined() {
+            self.inline_ptr_mut()
+        } else {
+            self.heap().pointer
+        }
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        let (ptr, length) = self.data_parts();
+        unsafe { slice::from_raw_parts(ptr, length) }
+    }
+
+    #[inline]
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        let ptr = self.as_mut_ptr();
+        let length = self.len();
+        unsafe { slice::from_raw_parts_mut(ptr, length) }
+    }
+
+    /// Writes the whole string to `w` with `write_all`, returning the number of bytes written.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write + ?Sized>(&self, w: &mut W) -> std::io::Result<usize> {
+        w.write_all(self.as_bytes())?;
+        Ok(self.len())
+    }
+
+    /// Keeps only the ASCII bytes for which `f` returns true, compacting the buffer in place. Faster than
+    /// a char-based retain for ASCII content. `f` is only called for ASCII bytes; non-ASCII bytes are
+    /// always kept, so removing bytes can never split a char and leave invalid UTF-8 behind.
+    /// Static strings are copied first.
+    pub fn retain_ascii<F: FnMut(u8) -> bool>(&mut self, mut f: F) {
+        self.ensure_owned();
+        let len = self.len();
+        let mut guard = RetainGuard { string: self, len, read: 0, kept: 0 };
+        let ptr = guard.string.as_mut_ptr();
+
+        while guard.read < len {
+            let b = unsafe { ptr.add(guard.read).read() };
+            if !b.is_ascii() || f(b) {
+                unsafe { ptr.add(guard.kept).write(b) };
+                guard.kept += 1;
+            }
+            guard.read += 1;
+        }
+    }
+
+    /// Removes every non-overlapping occurrence of `pat` in one compaction pass, instead of a
+    /// `remove`-per-match loop. An empty `pat` removes nothing. Static strings are copied first.
+    pub fn remove_matches(&mut self, pat: &str) {
+        if pat.is_empty() {
+            return;
+        }
+        self.ensure_owned();
+        let len = self.len();
+        let ptr = self.as_mut_ptr();
+
+        let mut read = 0;
+        let mut kept = 0;
+        while read < len {
+            let rest = unsafe { str::from_utf8_unchecked(slice::from_raw_parts(ptr.add(read), len - read)) };
+            let run = rest.find(pat).unwrap_or(rest.len());
+            unsafe { ptr.add(kept).copy_from(ptr.add(read), run) };
+            kept += run;
+            read += run + pat.len();
+        }
+        self.update_len(kept);
+    }
+
+    /// Collapses every run of consecutive `c` into a single `c`, like `tr -s`, e.g. `//` to `/` in
+    /// paths. Static strings are only copied if there's a run to collapse.
+    pub fn squeeze(&mut self, c: char) {
+        let mut buf = [0; 8];
+        let n = c.encode_utf8(&mut buf).len();
+        buf.copy_within(..n, n);
+        let pair = unsafe { str::from_utf8_unchecked(&buf[..2 * n]) };
+        if !self.as_str().contains(pair) {
+            return;
+        }
+
+        self.ensure_owned();
+        let len = self.len();
+        let ptr = self.as_mut_ptr();
+        let pat = &pair.as_bytes()[..n];
+
+        let mut read = 0;
+        let mut kept = 0;
+        let mut prev_was_c = false;
+        while read < len {
+            // `pat` starts with a lead byte, so it can only match on a char boundary
+            let is_c = unsafe { slice::from_raw_parts(ptr.add(read), len - read) }.starts_with(pat);
+            let step = if is_c { n } else { 1 };
+            if !(is_c && prev_was_c) {
+                unsafe { ptr.add(kept).copy_from(ptr.add(read), step) };
+                kept += step;
+            }
+            prev_was_c = is_c;
+            read += step;
+        }
+        self.update_len(kept);
+    }
+
+    /// Copy with runs of `c` collapsed, see `squeeze`. A static string without runs stays shared.
+    pub fn squeezed(&self, c: char) -> Self {
+        let mut string = self.clone();
+        string.squeeze(c);
+        string
+    }
+
+    /// Keeps only the bytes inside `ranges`, concatenated in order, in one compaction pass. The
+    /// complement of `match_ranges` + `remove_matches`. Static strings are copied first.
+    ///
+    /// Panics if the ranges aren't sorted, overlap, are out of bounds or don't lie on char boundaries.
+    /// The string is then left empty, as it is if the `ranges` iterator panics.
+    pub fn retain_ranges<I: IntoIterator<Item = core::ops::Range<usize>>>(&mut self, ranges: I) {
+        self.ensure_owned();
+        let len = self.len();
+        // Ranges are checked as they're consumed, after earlier ones were already moved. Until the
+        // compaction finishes the string is empty, so a panic can't expose a half-moved buffer
+        self.update_len(0);
+        let ptr = self.as_mut_ptr();
+        // Continuation bytes are 0b10xx_xxxx
+        let is_boundary = |i: usize| i == len || (unsafe { ptr.add(i).read() } as i8) >= -0x40;
+
+        let mut prev_end = 0;
+        let mut kept = 0;
+        for range in ranges {
+            assert!(
+                prev_end <= range.start && range.start <= range.end && range.end <= len,
+                "ranges must be sorted, non-overlapping and within {} bytes", len,
+            );
+            assert!(is_boundary(range.start) && is_boundary(range.end), "range does not lie on char boundaries");
+            unsafe { ptr.add(kept).copy_from(ptr.add(range.start), range.len()) };
+            kept += range.len();
+            prev_end = range.end;
+        }
+        self.update_len(kept);
+    }
+
+    /// Converts ASCII letters to uppercase in place. Static strings are copied first.
+    pub fn make_ascii_uppercase(&mut self) {
+        self.as_bytes_mut().make_ascii_uppercase();
+    }
+
+    /// Converts ASCII letters to lowercase in place. Static strings are copied first.
+    pub fn make_ascii_lowercase(&mut self) {
+        self.as_bytes_mut().make_ascii_lowercase();
+    }
+
+    /// Returns an uppercased copy, leaving `self` untouched. Short results stay inline.
+    pub fn to_ascii_uppercase(&self) -> Self {
+        let mut string = Self::copy_from(self.as_str());
+        string.make_ascii_uppercase();
+        string
+    }
+
+    /// Returns a lowercased copy, leaving `self` untouched. Short results stay inline.
+    pub fn to_ascii_lowercase(&self) -> Self {
+        let mut string = Self::copy_from(self.as_str());
+        string.make_ascii_lowercase();
+        string
+    }
+
+    /// Unicode uppercase copy, like `str::to_uppercase` (e.g. 'ß' becomes "SS"). Short results stay inline.
+    pub fn to_uppercase(&self) -> Self {
+        self.chars().flat_map(char::to_uppercase).collect()
+    }
+
+    /// Unicode lowercase copy, like `str::to_lowercase`. Short results stay inline.
+    pub fn to_lowercase(&self) -> Self {
+        // Lowercasing is context free except for a word-final 'Σ', so leave that case to std
+        if self.as_str().contains('Σ') {
+            return Self::from(self.as_str().to_lowercase());
+        }
+        self.chars().flat_map(char::to_lowercase).collect()
+    }
+
+    /// Converts the string into its bytes. Heap strings hand over their buffer without copying;
+    /// inline and static strings have no owned buffer, so their bytes are copied into a new `Vec`.
+    pub fn into_bytes(self) -> Vec<u8> {
+        if !self.is_heap_allocated() {
+            return self.as_bytes().to_vec();
+        }
+
+        let string = mem::ManuallyDrop::new(self);
+        unsafe { Vec::from_raw_parts(string.heap().pointer, string.heap().length, string.capacity()) }
+    }
+
+    /// Converts into a `String`, transferring the buffer of heap strings without copying.
+    #[inline]
+    pub fn into_string(self) -> String {
+        unsafe { String::from_utf8_unchecked(self.into_bytes()) }
+    }
+
+    /// Converts into a `Box<str>`. Heap buffers are shrunk to `len()` in place rather than copied.
+    #[inline]
+    pub fn into_boxed_str(self) -> Box<str> {
+        self.into_string().into_boxed_str()
+    }
+
+    /// Leaks the string, returning a mutable reference to its contents that lives as long as
+    /// the caller wants, like `String::leak`. The buffer is never freed, excess capacity included.
+    /// Inline and static strings have no buffer of their own, so their bytes are first copied
+    /// into a new heap allocation.
+    pub fn leak<'a>(mut self) -> &'a mut str {
+        self.force_heap_relocation(self.len());
+
+        let string = mem::ManuallyDrop::new(self);
+        unsafe { str::from_utf8_unchecked_mut(slice::from_raw_parts_mut(string.heap().pointer, string.heap().length)) }
+    }
+
+    /// Decomposes the string into `(pointer, length, capacity)` of a heap buffer, e.g. to hand it
+    /// across FFI. Inline and static strings are first copied into an exactly sized heap buffer.
+    /// The caller owns the buffer afterwards; rebuild the string with `from_raw_parts` to free it.
+    pub fn into_raw_parts(mut self) -> (*mut u8, usize, usize) {
+        self.force_heap_relocation(self.len());
+
+        let string = mem::ManuallyDrop::new(self);
+        (string.heap().pointer, string.heap().length, string.capacity())
+    }
+
+    /// Rebuilds a heap string from its raw parts.
+    ///
+    /// # Safety
+    /// - If `capacity` is nonzero, `ptr` must have been allocated by the global allocator with
+    ///   a size of `capacity` and an alignment of 1, as `into_raw_parts`, `String` and `Vec<u8>` do.
+    ///   If `capacity` is 0, `ptr` must still be non-null (e.g. `NonNull::dangling()`).
+    /// - `length <= capacity` and the first `length` bytes must be valid UTF-8.
+    /// - The buffer must not be used or freed elsewhere afterwards.
+    ///
+    /// # Panics
+    /// Panics if `capacity` doesn't fit the shifted capacity field (`MAX_CAPACITY`).
+    pub unsafe fn from_raw_parts(ptr: *mut u8, length: usize, capacity: usize) -> Self {
+        assert!(capacity <= Self::MAX_CAPACITY, "capacity overflow");
+        debug_assert!(!ptr.is_null(), "from_raw_parts called with a null pointer");
+        debug_assert!(length <= capacity);
+
+        Self::from_heap_parts(capacity << 8, length, ptr)
+    }
+
+    /// Returns the content bytes in reverse order. This is a byte-level reversal intended
+    /// for hashing/partitioning, so the result is generally not valid UTF-8.
+    pub fn reversed_bytes(&self) -> Vec<u8> {
+        self.as_bytes().iter().rev().copied().collect()
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(self.as_bytes()) }
+    }
+
+    /// Static strings are copied first, so the returned slice never points into read-only memory.
+    #[inline]
+    pub fn as_mut_str(&mut self) -> &mut str {
+        unsafe { str::from_utf8_unchecked_mut(self.as_bytes_mut()) }
+    }
+
+    /// Explicit copy-on-write point, like `Rc::make_mut`: a static string is copied into an owned inline
+    /// or heap string, leaving the static data untouched. Owned strings are returned as they are.
+    #[inline]
+    pub fn make_mut(&mut self) -> &mut str {
+        self.ensure_owned();
+        self.as_mut_str()
+    }
+
+    /// Swaps the contents of two strings in O(1) without allocating, like `mem::swap`. This is sound
+    /// for every representation: inline bytes travel with the struct, and `as_ptr`/`as_str` derive
+    /// the inline data pointer from the struct's current address on every call, never a cached one.
+    /// Static strings stay shared and heap buffers just change owner.
+    #[inline]
+    pub fn swap(&mut self, other: &mut Self) {
+        mem::swap(self, other);
+    }
+
+    /// Returns the subslice for `index`, or `None` if it's out of bounds or not on char boundaries.
+    #[inline]
+    pub fn get<I: SliceIndex<str>>(&self, index: I) -> Option<&I::Output> {
+        self.as_str().get(index)
+    }
+
+    /// Mutable version of `get`. Static strings are copied first.
+    #[inline]
+    pub fn get_mut<I: SliceIndex<str>>(&mut self, index: I) -> Option<&mut I::Output> {
+        self.as_mut_str().get_mut(index)
+    }
+
+    /// `as_str` for a string known to be heap or static, skipping the representation branch.
+    ///
+    /// # Safety
+    /// The string must not be inline. Checked in debug builds only.
+    #[inline]
+    pub unsafe fn heap_str(&self) -> &str {
+        debug_assert!(!self.is_inlined(), "heap_str called on an inline string");
+        unsafe { self.as_str_assume::<{ SsosPrecond::Heap.into_param() }>() }
+    }
+
+    /// `as_str` for a string known to be inline, skipping the representation branch.
+    ///
+    /// # Safety
+    /// The string must be inline. Checked in debug builds only.
+    #[inline]
+    pub unsafe fn inline_str(&self) -> &str {
+        debug_assert!(self.is_inlined(), "inline_str called on a heap or static string");
+        unsafe { self.as_str_assume::<{ SsosPrecond::Inline.into_param() }>() }
+    }
+
+    /// # Safety
+    /// The string must currently be in the state described by `PRECOND`. `Ascii` makes no assumption
+    /// about the representation, but requires the contents to be ASCII. It is equivalent to `as_str`.
+    #[inline]
+    pub unsafe fn as_str_assume<const PRECOND: SsosPrecondType>(&self) -> &str {
+        let assumption= SsosPrecond::from(PRECOND);
+        match assumption {
+            SsosPrecond::Heap |
+            SsosPrecond::HeapAssumeCapacity |
+            SsosPrecond::Static => {
+                unsafe {
+                    core::str::from_utf8_unchecked(core::slice::from_raw_parts(self.heap().pointer, self.heap().length))
+                }
+            }
+            SsosPrecond::Inline |
+            SsosPrecond::InlineAssumeCapacity => {
+                unsafe {
+                    let md = (self as *const Self as *const u8).read();
+                    let ptr = (self as *const Self as *const u8).add(1);
+                    let len = (md & 0b00_111111) as usize;
+                    core::str::from_utf8_unchecked(core::slice::from_raw_parts(ptr, len))
+                }
+            }
+            SsosPrecond::Ascii => {
+                debug_assert!(self.as_bytes().is_ascii(), "as_str_assume::<Ascii> called on non-ASCII contents");
+                self.as_str()
+            }
+        }
+    }
+
+    /// Panics if the representation invariants don't hold: inline strings fit the inline buffer and
+    /// don't carry the static flag, heap and static strings have a non-null pointer and
+    /// `length <= capacity`, and no stray metadata bits are set. Debug builds check this after every
+    /// representation change; it's public so tests and fuzzers can check after arbitrary operations.
+    pub fn validate(&self) {
+        let md = self.metadata();
+        if md.is_inlined() {
+            assert!(!md.is_static(), "inline string has the static flag set");
+            assert!(
+                md.inline_len() as usize <= Self::INLINE_CAPACITY,
+                "inline length {} exceeds the inline capacity {}", md.inline_len(), Self::INLINE_CAPACITY,
+            );
+            return;
+        }
+
+        let heap = self.heap();
+        assert!(!heap.pointer.is_null(), "heap or static string has a null pointer");
+        assert!(heap.length <= heap.capacity >> 8, "length {} exceeds capacity {}", heap.length, heap.capacity >> 8);
+        if md.is_static() {
+            assert_eq!(md.data & 0b100_11111, 0, "static string has stray metadata bits");
+        } else {
+            assert_eq!(md.data, 0, "heap string has metadata bits set");
+        }
+    }
+
+    #[inline(always)]
+    fn debug_validate(&self) {
+        if cfg!(debug_assertions) {
+            self.validate();
+        }
+    }
+
+    /// The raw metadata byte, for fuzzing and invariant checks. Not a stable API.
+    #[doc(hidden)]
+    #[inline]
+    pub fn raw_metadata(&self) -> u8 {
+        self.metadata().data
+    }
+
+    /// The metadata byte decoded as `(is_inlined, is_static, inline_len)`. `inline_len` is only
+    /// meaningful for inline strings. Not a stable API.
+    #[doc(hidden)]
+    #[inline]
+    pub fn decoded_metadata(&self) -> (bool, bool, u8) {
+        let md = self.metadata();
+        (md.is_inlined(), md.is_static(), md.inline_len())
+    }
+
+    /// Data pointer and length of any representation. The metadata byte is read once and both
+    /// candidates are computed up front, so the choice compiles to conditional moves rather than a
+    /// branch that mispredicts on mixed inline/heap data. Reading the heap view of an inline string
+    /// is fine: every byte of the struct is initialized, the unused values are just discarded.
+    #[inline(always)]
+    fn data_parts(&self) -> (*const u8, usize) {
+        let md = self.metadata().data;
+        let is_inlined = md & 0b100_00000 != 0;
+        let heap = self.heap();
+        let ptr = hint::select_unpredictable(is_inlined, self.inline_ptr(), heap.pointer as *const u8);
+        let len = hint::select_unpredictable(is_inlined, (md & 0b00_111111) as usize, heap.length);
+        (ptr, len)
+    }
+
+    #[inline]
+    fn metadata(&self) -> &SsoStrMetadata {
+        let metadata = self as *const Self as *const SsoStrMetadata;
+        unsafe { metadata.as_ref().unwrap_unchecked() }
+    }
+
+
+    #[inline]
+    const fn metadata_mut(&mut self) -> &mut SsoStrMetadata {
+        let metadata = self as *mut Self as *mut SsoStrMetadata;
+        unsafe { metadata.as_mut().unwrap_unchecked() }
+    }
+
+    #[inline]
+    fn inline_ptr(&self) -> *const u8 {
+        let ptr = self as *const Self as *const u8;
+        unsafe { ptr.add(1) }
+    }
+
+    #[inline]
+    const fn inline_ptr_mut(&mut self) -> *mut u8 {
+        let ptr = self as *mut Self as *mut u8;
+        unsafe { ptr.add(1) }
+    }
+
+    #[inline]
+    fn set_capacity(&mut self, capacity: usize) {
+        debug_assert!(capacity <= Self::MAX_CAPACITY, "capacity overflow");
+        match mem::size_of::<usize>() {
+            4 => self.heap_mut().capacity &= !Self::BIT_MASK_UPPER_U32_24 as usize,
+            8 => self.heap_mut().capacity &= !Self::BIT_MASK_UPPER_U64_56 as usize,
+            _ => unsafe { hint::unreachable_unchecked() }
+        }
+        
+        self.heap_mut().capacity |= capacity << 8;
+    }
+
+    /// Layout of a heap buffer. Buffers are plain bytes, so they use alignment 1, which is also what
+    /// `Vec<u8>`/`String` allocate with; this keeps adopted buffers and our own deallocation in agreement.
+    #[inline]
+    fn heap_layout(capacity: usize) -> Layout {
+        unsafe { Layout::from_size_align_unchecked(capacity, 1) }
+    }
+
+    /// Copy-on-write step: a static string gets its own copy of the data (inline if it fits) so it can be mutated.
+    #[inline]
+    fn ensure_owned(&mut self) {
+        if self.is_static() {
+            *self = Self::copy_from(self.as_str());
+            self.debug_validate();
+        }
+    }
+
+    /// Layout of a heap buffer, checking that the capacity is representable: it must fit the shifted
+    /// capacity field and a valid `Layout`.
+    #[inline]
+    fn try_heap_layout(capacity: usize) -> Result<Layout, TryReserveError> {
+        if capacity > Self::MAX_CAPACITY {
+            return Err(TryReserveError::CapacityOverflow);
+        }
+        Layout::from_size_align(capacity, 1).map_err(|_| TryReserveError::CapacityOverflow)
+    }
+
+    /// Allocates a heap buffer. Zero-sized allocations aren't allowed, so a capacity of 0 yields a
+    /// dangling pointer instead (`Drop` never frees a zero capacity buffer).
+    fn try_alloc_heap(capacity: usize) -> Result<*mut u8, TryReserveError> {
+        if capacity == 0 {
+            return Ok(ptr::NonNull::dangling().as_ptr());
+        }
+        let layout = Self::try_heap_layout(capacity)?;
+        let ptr = unsafe { alloc(layout) };
+        if ptr.is_null() {
+            return Err(TryReserveError::AllocError { layout });
+        }
+        Ok(ptr)
+    }
+
+    #[inline]
+    fn alloc_heap(capacity: usize) -> *mut u8 {
+        Self::try_alloc_heap(capacity).unwrap_or_else(|e| e.handle())
+    }
+
+    /// Resizes the owned heap buffer to `new_capacity`, allocating a fresh one if no buffer was allocated yet.
+    ///
+    /// Safety: the string must be a mutable heap string.
+    unsafe fn try_resize_heap(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        let old_capacity = self.heap().capacity >> 8;
+        if old_capacity == 0 || new_capacity == 0 {
+            let ptr = Self::try_alloc_heap(new_capacity)?;
+            unsafe { ptr.copy_from_nonoverlapping(self.heap().pointer, self.heap().length.min(new_capacity)) };
+            if old_capacity != 0 {
+                unsafe { dealloc(self.heap().pointer, Self::heap_layout(old_capacity)) };
+            }
+            self.heap_mut().pointer = ptr;
+        } else {
+            let layout = Self::try_heap_layout(new_capacity)?;
+            let ptr = unsafe { realloc(self.heap().pointer, Self::heap_layout(old_capacity), new_capacity) };
+            if ptr.is_null() {
+                return Err(TryReserveError::AllocError { layout });
+            }
+            self.heap_mut().pointer = ptr;
+        }
+        self.set_capacity(new_capacity);
+        self.debug_validate();
+        Ok(())
+    }
+
+    /// Safety: the string must be a mutable heap string.
+    #[inline]
+    unsafe fn resize_heap(&mut self, new_capacity: usize) {
+        if let Err(e) = unsafe { self.try_resize_heap(new_capacity) } {
+            e.handle();
+        }
+    }
+
+    /// Capacity to grow to when `needed` bytes don't fit in `current`: `current` scaled by the growth
+    /// factor (1.5, or 2 with the `growth_2x` feature), but at least `needed`. The scaled value saturates
+    /// at the largest encodable capacity, so growth alone never turns a valid request into an overflow.
+    #[inline]
+    fn grow_capacity(current: usize, needed: usize) -> usize {
+        let grown = if cfg!(feature = "growth_2x") {
+            current.saturating_mul(2)
+        } else {
+            current.saturating_add(current >> 1)
+        };
+        grown.min(Self::MAX_CAPACITY).max(needed)
+    }
+
+    /// Pushes `count` copies of `fill` after a single reservation.
+    fn push_fill(&mut self, count: usize, fill: char) {
+        if count == 0 {
+            return;
+        }
+        let mut buf = [0; 4];
+        let fill = fill.encode_utf8(&mut buf);
+        let additional = count.checked_mul(fill.len()).unwrap_or_else(|| TryReserveError::CapacityOverflow.handle());
+        self.reserve(additional);
+        for _ in 0..count {
+            self.push_str(fill);
+        }
+    }
+
+    /// Owned copy of `sub`, which must be a slice of `self`. Static strings share their data instead of copying.
+    fn substring(&self, sub: &str) -> Self {
+        if self.is_static() {
+            let mut string = unsafe { Self::from_static_unchecked(sub) };
+            string.metadata_mut().set_is_static_lifetime(self.metadata().is_static_lifetime() as u8);
+            string
+        } else {
+            Self::from(sub)
+        }
+    }
+
+    /// Grows any representation into a heap buffer of `new_capacity` bytes.
+    fn try_grow_to(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        if !self.try_force_heap_relocation(new_capacity)? {
+            unsafe { self.try_resize_heap(new_capacity)? };
+        }
+        Ok(())
+    }
+
+    /// Resolves `range` against the current length, panicking if it's out of bounds or splits a char.
+    fn char_range<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n.checked_add(1).expect("range start overflows usize"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n.checked_add(1).expect("range end overflows usize"),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => len,
+        };
+
+        assert!(start <= end, "range start {} is greater than range end {}", start, end);
+        assert!(end <= len, "range end {} is out of bounds for a string of length {}", end, len);
+        let s = self.as_str();
+        assert!(s.is_char_boundary(start), "range start {} does not lie on a char boundary", start);
+        assert!(s.is_char_boundary(end), "range end {} does not lie on a char boundary", end);
+        (start, end)
+    }
+
+    /// Sets the length of the active representation without touching the underlying bytes.
+    #[inline]
+    fn update_len(&mut self, new_len: usize) {
+        if self.is_inlined() {
+            self.metadata_mut().set_inline_len(new_len as u8);
+        } else {
+            self.heap_mut().length = new_len;
+        }
+        self.debug_validate();
+    }
+
+    #[inline]
+    fn is_heap_allocated(&self) -> bool {
+        let md = self.metadata().data;
+        md >> 6 == 0
+    }
+
+    /// Does nothing if the string is already heap-allocated. The new buffer holds at least `len()`
+    /// bytes, whatever `capacity` asks for.
+    fn force_heap_relocation(&mut self, capacity: usize) -> bool {
+        self.try_force_heap_relocation(capacity).unwrap_or_else(|e| e.handle())
+    }
+
+    fn try_force_heap_relocation(&mut self, capacity: usize) -> Result<bool, TryReserveError> {
+        if self.is_heap_allocated() {
+            return Ok(false);
+        }
+
+        let capacity = capacity.max(self.len());
+        let ptr = Self::try_alloc_heap(capacity)?;
+        let placeholder = self.clone();
+        debug_assert!(placeholder.len() <= capacity);
+
+        self.set_capacity(capacity);
+        self.metadata_mut().zero_all();
+
+        self.heap_mut().length = placeholder.len();
+        self.heap_mut().pointer = ptr;
+
+        let src_pointer = placeholder.as_ptr();
+        unsafe { 
+            ptr.copy_from_nonoverlapping(src_pointer, placeholder.len()) 
+        };
+        self.debug_validate();
+        Ok(true)
+    }
+
+    const fn null_string() -> Self {
+        let () = Self::VALID_INLINE_CAPACITY;
+        // All zeroes is a null pointer with no flags set, and leaves no byte of the union uninitialized
+        unsafe { mem::zeroed() }
+    }
+
+    #[inline]
+    fn from_heap_parts(capacity: usize, length: usize, pointer: *mut u8) -> Self {
+        let mut string = Self::null_string();
+        *string.heap_mut() = HeapRepr { capacity, length, pointer };
+        string
+    }
+
+    #[inline(always)]
+    fn heap(&self) -> &HeapRepr {
+        // SAFETY: every byte of `repr` is initialized and any bit pattern is a valid `HeapRepr`
+        unsafe { &self.repr.heap }
+    }
+
+    #[inline(always)]
+    fn heap_mut(&mut self) -> &mut HeapRepr {
+        // SAFETY: as in `heap`
+        unsafe { &mut self.repr.heap }
+    }
+}
+
+impl<const N: usize> Default for SsoStringN<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for SsoStringN<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            // Detailed, pretty-printed output for "{:#?}"
+            f.debug_struct("SsoString")
+                .field("content", &self.as_str()) // self.as_str() will use str's Debug impl (quoted, escaped)
+                .field("len", &self.len())
+                .field("capacity", &self.capacity())
+                .field("is_inlined", &self.is_inlined())
+                .field("is_static_flag", &(self.metadata().is_static()))
+                .field("metadata_byte", &self.metadata().data) // Raw metadata byte for deeper inspection
+                .finish()
+        } else {
+            // Default output for "{:?}": mimic String's debug output
+            // This defers to the Debug implementation for &str,
+            // which prints the string quoted and with escaped characters.
+            fmt::Debug::fmt(self.as_str(), f)
+        }
+    }
+}
+
+impl<const N: usize> fmt::Display for SsoStringN<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Write for SsoStringN<N> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push(c);
+        Ok(())
+    }
+}
+
+/// Like `format!`, but formats into a new `SsoString`. Short results stay inline and never allocate.
+#[macro_export]
+macro_rules! format_sso {
+    ($($arg:tt)*) => {{
+        let mut string = $crate::SsoString::new();
+        ::core::fmt::Write::write_fmt(&mut string, ::core::format_args!($($arg)*))
+            .expect("a formatting trait implementation returned an error");
+        string
+    }};
+}
+
+impl<const N: usize> Clone for SsoStringN<N> {
+    fn clone(&self) -> Self {
+        let mut new_string: Self = unsafe { (self as *const Self).read() };
+        
+        if self.is_heap_allocated() && self.capacity() != 0 {
+            let ptr = Self::alloc_heap(self.capacity());
+            unsafe { ptr.copy_from_nonoverlapping(self.heap().pointer, self.len()) };
+            new_string.heap_mut().pointer = ptr;
+        }
+        new_string
+    }
+
+    /// Copies into the existing heap buffer when both strings are on the heap and it's big enough.
+    fn clone_from(&mut self, source: &Self) {
+        if self.is_heap_allocated() && source.is_heap_allocated() && self.capacity() >= source.len() {
+            unsafe { self.heap().pointer.copy_from_nonoverlapping(source.heap().pointer, source.len()) };
+            self.heap_mut().length = source.len();
+        } else {
+            *self = source.clone();
+        }
+    }
+}
+
+impl<const N: usize> Drop for SsoStringN<N> {
+    fn drop(&mut self) {
+        // A zero capacity heap string never allocated, its pointer is dangling
+        if self.is_heap_allocated() && self.capacity() != 0 {
+            let layout = Self::heap_layout(self.capacity());
+            unsafe { dealloc(self.heap().pointer, layout) };
+        }
+    }
+}
+
+// SAFETY: a heap `SsoString` uniquely owns its buffer, like `String`, and inline strings carry
+// their bytes by value. Static strings point at `'static` data that is only ever read; every
+// `&mut self` path copies it out first (`ensure_owned`), so no shared buffer is ever written.
+unsafe impl<const N: usize> Send for SsoStringN<N> {}
+
+// SAFETY: `&SsoString` only allows reads of the buffer, see `Send` above.
+unsafe impl<const N: usize> Sync for SsoStringN<N> {}
+
+impl<const N: usize> Hash for SsoStringN<N> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl<const N: usize> Borrow<str> for SsoStringN<N> {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<str> for SsoStringN<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for SsoStringN<N> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> AsRef<std::ffi::OsStr> for SsoStringN<N> {
+    #[inline]
+    fn as_ref(&self) -> &std::ffi::OsStr {
+        self.as_str().as_ref()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> AsRef<std::path::Path> for SsoStringN<N> {
+    #[inline]
+    fn as_ref(&self) -> &std::path::Path {
+        self.as_str().as_ref()
+    }
+}
+
+impl<const N: usize> From<SsoStringN<N>> for String {
+    #[inline]
+    fn from(value: SsoStringN<N>) -> Self {
+        value.into_string()
+    }
+}
+
+impl<const N: usize> From<&str> for SsoStringN<N> {
+    #[inline]
+    fn from(value: &str) -> Self {
+        Self::copy_from(value)
     }
+}
 
+impl<const N: usize> From<&String> for SsoStringN<N> {
     #[inline]
-    fn set_capacity(&mut self, capacity: usize) {
-        match mem::size_of::<usize>() {
-            4 => self.capacity = self.capacity & (!Self::BIT_MASK_UPPER_U32_24 as usize),
-            8 => self.capacity = self.capacity & (!Self::BIT_MASK_UPPER_U64_56 as usize),
-            _ => unsafe { hint::unreachable_unchecked() }
-        }
-        
-        self.capacity = self.capacity | (capacity << 8);
+    fn from(value: &String) -> Self {
+        Self::copy_from(value)
     }
+}
 
+impl<const N: usize> From<&&str> for SsoStringN<N> {
     #[inline]
-    fn is_heap_allocated(&self) -> bool {
-        let md = self.metadata().data;
-        md >> 6 == 0
+    fn from(value: &&str) -> Self {
+        Self::copy_from(value)
     }
+}
 
-    /// Does nothing if the string is already heap-allocated.
-    fn force_heap_relocation(&mut self, capacity: usize) -> bool {
-        if self.is_heap_allocated() {
-            return false;
+/// Borrowed data is copied; owned data goes through `From<String>` and keeps its buffer.
+impl<const N: usize> From<Cow<'_, str>> for SsoStringN<N> {
+    #[inline]
+    fn from(value: Cow<'_, str>) -> Self {
+        match value {
+            Cow::Borrowed(s) => Self::copy_from(s),
+            Cow::Owned(s) => Self::from(s),
         }
+    }
+}
 
-        let placeholder = self.clone();
-        let layout = Layout::from_size_align(capacity, 4)
-            .unwrap();
-        let ptr = unsafe { alloc::alloc(layout) };
-
-        self.set_capacity(capacity);
-        self.metadata_mut().zero_flags();
-
-        self.length = placeholder.len();
-        self.pointer = ptr;
-
-        let src_pointer = placeholder.as_ptr();
-        unsafe { 
-            ptr.copy_from_nonoverlapping(src_pointer, placeholder.len()) 
-        };
-        true
+impl<const N: usize> From<&SsoStringN<N>> for SsoStringN<N> {
+    #[inline]
+    fn from(value: &Self) -> Self {
+        value.clone()
     }
+}
 
-    const fn null_string() -> Self {
-        SsoString { capacity: 0, length: 0, pointer: 0 as *mut u8 }
+/// A single char is at most 4 bytes, so this never allocates.
+impl<const N: usize> From<char> for SsoStringN<N> {
+    #[inline]
+    fn from(value: char) -> Self {
+        Self::copy_from(value.encode_utf8(&mut [0; 4]))
     }
 }
 
-impl Default for SsoString {
-    fn default() -> Self {
-        let mut string = Self::null_string();
-        string.metadata_mut().set_is_inlined(1);
-        string
+impl<const N: usize> From<&[char]> for SsoStringN<N> {
+    fn from(value: &[char]) -> Self {
+        value.iter().copied().collect()
     }
 }
 
-impl fmt::Debug for SsoString {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if f.alternate() {
-            // Detailed, pretty-printed output for "{:#?}"
-            f.debug_struct("SsoString")
-                .field("content", &self.as_str()) // self.as_str() will use str's Debug impl (quoted, escaped)
-                .field("len", &self.len())
-                .field("capacity", &self.capacity())
-                .field("is_inlined", &self.is_inlined())
-                .field("is_static_flag", &(self.metadata().is_static()))
-                .field("metadata_byte", &self.metadata().data) // Raw metadata byte for deeper inspection
-                .finish()
-        } else {
-            // Default output for "{:?}": mimic String's debug output
-            // This defers to the Debug implementation for &str,
-            // which prints the string quoted and with escaped characters.
-            fmt::Debug::fmt(self.as_str(), f)
-        }
+impl<const N: usize> TryFrom<&[u8]> for SsoStringN<N> {
+    type Error = str::Utf8Error;
+
+    #[inline]
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        str::from_utf8(value).map(Self::copy_from)
     }
 }
 
-impl Clone for SsoString {
-    fn clone(&self) -> Self {
-        let mut new_string: SsoString = unsafe { (self as *const SsoString).read() };
-        
-        if self.is_heap_allocated()  {
-            let layout = unsafe { Layout::from_size_align_unchecked(self.capacity(), 4) };
-            let ptr = unsafe { alloc::alloc(layout) };
-            unsafe { ptr.copy_from_nonoverlapping(self.pointer, self.len()) };
-            new_string.pointer = ptr;
-        }
-        new_string
+/// Reuses the vector's buffer, see `SsoString::from_utf8`.
+impl<const N: usize> TryFrom<Vec<u8>> for SsoStringN<N> {
+    type Error = str::Utf8Error;
+
+    #[inline]
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::from_utf8(value)
     }
 }
 
-impl Drop for SsoString {
-    fn drop(&mut self) {
-        if self.is_heap_allocated() {
-            let layout = unsafe { Layout::from_size_align_unchecked(self.capacity(), 4) };
-            unsafe { alloc::dealloc(self.pointer, layout) };
+/// Short strings are inlined; longer ones take ownership of the `String`'s buffer without copying,
+/// unless its capacity exceeds `MAX_CAPACITY`.
+impl<const N: usize> From<String> for SsoStringN<N> {
+    fn from(value: String) -> Self {
+        if value.len() <= Self::INLINE_CAPACITY {
+            return Self::copy_from(&value);
         }
+
+        unsafe { Self::adopt_buffer(value.into_bytes(), Self::MAX_CAPACITY) }
     }
 }
 
-impl Hash for SsoString {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        state.write(self.as_bytes());
+/// Short boxes are inlined; longer ones hand over their allocation, with capacity equal to the length.
+impl<const N: usize> From<Box<str>> for SsoStringN<N> {
+    #[inline]
+    fn from(value: Box<str>) -> Self {
+        Self::from(value.into_string())
     }
 }
 
-impl AsRef<str> for SsoString {
-    fn as_ref(&self) -> &str {
-        self.as_str()
+impl<const N: usize> core::str::FromStr for SsoStringN<N> {
+    type Err = core::convert::Infallible;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
     }
 }
 
-impl Into<String> for SsoString {
-    fn into(self) -> String {
-        self.to_string()
+impl<const N: usize> PartialEq for SsoStringN<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_str(other.as_str())
     }
 }
 
-impl From<&'static str> for SsoString {
-    fn from(value: &'static str) -> Self {
-        Self::from_static(value)
+/// Compares 16 bytes at a time (SSE2 is baseline on x86_64), then the tail as a slice.
+/// A single chunk covers most of an inline string.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[inline]
+fn bytes_eq_sse2(a: &[u8], b: &[u8]) -> bool {
+    use core::arch::x86_64::{__m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8};
+
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut a_chunks = a.chunks_exact(16);
+    let mut b_chunks = b.chunks_exact(16);
+    for (x, y) in (&mut a_chunks).zip(&mut b_chunks) {
+        // SAFETY: SSE2 is always available on x86_64, both chunks are exactly 16 bytes and
+        // `loadu` has no alignment requirement
+        let mask = unsafe {
+            let x = _mm_loadu_si128(x.as_ptr() as *const __m128i);
+            let y = _mm_loadu_si128(y.as_ptr() as *const __m128i);
+            _mm_movemask_epi8(_mm_cmpeq_epi8(x, y))
+        };
+        if mask != 0xFFFF {
+            return false;
+        }
     }
+    a_chunks.remainder() == b_chunks.remainder()
 }
 
-impl From<String> for SsoString {
-    fn from(value: String) -> Self {
-        let mut value = mem::ManuallyDrop::new(value);
+/// Skips all-ASCII 64-byte blocks with SSE2. From a block with a high bit set, the next `SCALAR_SPAN`
+/// bytes go to the scalar validator (non-ASCII text tends to cluster, and short calls cost more than
+/// they save), extended over any continuation bytes that spill past it, so every scalar call starts
+/// and ends on a char boundary and the ASCII scan can resume right after.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn validate_utf8_sse2(bytes: &[u8]) -> Result<(), str::Utf8Error> {
+    use core::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_movemask_epi8, _mm_or_si128};
+
+    const BLOCK: usize = 64;
+    const SCALAR_SPAN: usize = 1024;
+
+    let mut i = 0;
+    while i + BLOCK <= bytes.len() {
+        // SAFETY: SSE2 is always available on x86_64, `i + BLOCK` is in bounds and `loadu` has no
+        // alignment requirement
+        let high_bits = unsafe {
+            let ptr = bytes.as_ptr().add(i) as *const __m128i;
+            let or = _mm_or_si128(
+                _mm_or_si128(_mm_loadu_si128(ptr), _mm_loadu_si128(ptr.add(1))),
+                _mm_or_si128(_mm_loadu_si128(ptr.add(2)), _mm_loadu_si128(ptr.add(3))),
+            );
+            _mm_movemask_epi8(or)
+        };
+        if high_bits == 0 {
+            i += BLOCK;
+            continue;
+        }
 
-        Self {
-            capacity: value.capacity() << 8,
-            length: value.len(),
-            pointer: value.as_mut_ptr(),
+        let mut end = (i + SCALAR_SPAN).min(bytes.len());
+        // Continuation bytes are 0b10xx_xxxx
+        while end < bytes.len() && (bytes[end] as i8) < -0x40 {
+            end += 1;
         }
+        if str::from_utf8(&bytes[i..end]).is_err() {
+            // Rerun on the whole input so the error's offsets are relative to `bytes`
+            return str::from_utf8(bytes).map(|_| ());
+        }
+        i = end;
     }
-}
 
-impl PartialEq for SsoString {
-    fn eq(&self, other: &Self) -> bool {
-        self.as_str() == other.as_str()
+    match str::from_utf8(&bytes[i..]) {
+        Ok(_) => Ok(()),
+        Err(_) => str::from_utf8(bytes).map(|_| ()),
     }
 }
 
-impl Eq for SsoString {}
+impl<const N: usize> Eq for SsoStringN<N> {}
 
-impl PartialOrd for SsoString {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.as_str().partial_cmp(other.as_str())
+impl<const N: usize> PartialOrd for SsoStringN<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl Ord for SsoString {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+impl<const N: usize> Ord for SsoStringN<N> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.as_str().cmp(other.as_str())
     }
 }
 
-impl PartialEq<String> for SsoString {
+impl<const N: usize> PartialEq<String> for SsoStringN<N> {
     fn eq(&self, other: &String) -> bool {
         self.as_str() == other.as_str()
     }
 }
 
-impl PartialEq<&str> for SsoString {
+impl<const N: usize> PartialEq<&str> for SsoStringN<N> {
     fn eq(&self, other: &&str) -> bool {
         self.as_str() == *other
     }
 }
 
-impl PartialOrd<String> for SsoString {
-    fn partial_cmp(&self, other: &String) -> Option<std::cmp::Ordering> {
+impl<const N: usize> PartialOrd<String> for SsoStringN<N> {
+    fn partial_cmp(&self, other: &String) -> Option<core::cmp::Ordering> {
         self.as_str().partial_cmp(other.as_str())
     }
 }
 
-impl PartialOrd<&str> for SsoString {
-    fn partial_cmp(&self, other: &&str) -> Option<std::cmp::Ordering> {
+impl<const N: usize> PartialOrd<&str> for SsoStringN<N> {
+    fn partial_cmp(&self, other: &&str) -> Option<core::cmp::Ordering> {
+        self.as_str().partial_cmp(*other)
+    }
+}
+
+impl<const N: usize> PartialEq<str> for SsoStringN<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialOrd<str> for SsoStringN<N> {
+    fn partial_cmp(&self, other: &str) -> Option<core::cmp::Ordering> {
         self.as_str().partial_cmp(other)
     }
 }
 
-impl std::ops::Deref for SsoString {
+impl<const N: usize> PartialEq<SsoStringN<N>> for String {
+    fn eq(&self, other: &SsoStringN<N>) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<const N: usize> PartialEq<SsoStringN<N>> for str {
+    fn eq(&self, other: &SsoStringN<N>) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl<const N: usize> PartialEq<SsoStringN<N>> for &str {
+    fn eq(&self, other: &SsoStringN<N>) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl<const N: usize> PartialEq<Cow<'_, str>> for SsoStringN<N> {
+    fn eq(&self, other: &Cow<'_, str>) -> bool {
+        self.eq_str(other)
+    }
+}
+
+impl<const N: usize> PartialEq<Box<str>> for SsoStringN<N> {
+    fn eq(&self, other: &Box<str>) -> bool {
+        self.eq_str(other)
+    }
+}
+
+impl<const N: usize> PartialEq<SsoStringN<N>> for Cow<'_, str> {
+    fn eq(&self, other: &SsoStringN<N>) -> bool {
+        other.eq_str(self)
+    }
+}
+
+impl<const N: usize> PartialEq<SsoStringN<N>> for Box<str> {
+    fn eq(&self, other: &SsoStringN<N>) -> bool {
+        other.eq_str(self)
+    }
+}
+
+impl<const N: usize> PartialOrd<SsoStringN<N>> for String {
+    fn partial_cmp(&self, other: &SsoStringN<N>) -> Option<core::cmp::Ordering> {
+        self.as_str().partial_cmp(other.as_str())
+    }
+}
+
+impl<const N: usize> PartialOrd<SsoStringN<N>> for str {
+    fn partial_cmp(&self, other: &SsoStringN<N>) -> Option<core::cmp::Ordering> {
+        self.partial_cmp(other.as_str())
+    }
+}
+
+impl<const N: usize> PartialOrd<SsoStringN<N>> for &str {
+    fn partial_cmp(&self, other: &SsoStringN<N>) -> Option<core::cmp::Ordering> {
+        (*self).partial_cmp(other.as_str())
+    }
+}
+
+impl<const N: usize> core::ops::Deref for SsoStringN<N> {
     type Target = str;
     fn deref(&self) -> &Self::Target {
         self.as_str()
     }
 }
 
+macro_rules! impl_index {
+    ($($range:ty),*) => {
+        $(
+            impl<const N: usize> core::ops::Index<$range> for SsoStringN<N> {
+                type Output = str;
+
+                #[inline]
+                fn index(&self, index: $range) -> &str {
+                    &self.as_str()[index]
+                }
+            }
+
+            impl<const N: usize> core::ops::IndexMut<$range> for SsoStringN<N> {
+                #[inline]
+                fn index_mut(&mut self, index: $range) -> &mut str {
+                    &mut self.as_mut_str()[index]
+                }
+            }
+        )*
+    };
+}
+
+impl_index!(
+    core::ops::Range<usize>,
+    core::ops::RangeTo<usize>,
+    core::ops::RangeFrom<usize>,
+    core::ops::RangeFull,
+    core::ops::RangeInclusive<usize>,
+    core::ops::RangeToInclusive<usize>
+);
+
+impl<const N: usize> FromIterator<char> for SsoStringN<N> {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut string = Self::new();
+        string.extend(iter);
+        string
+    }
+}
+
+impl<'a, const N: usize> FromIterator<&'a str> for SsoStringN<N> {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut string = Self::new();
+        string.extend(iter);
+        string
+    }
+}
+
+impl<const N: usize> FromIterator<String> for SsoStringN<N> {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        let mut string = Self::new();
+        string.extend(iter);
+        string
+    }
+}
+
+impl<const N: usize> Extend<char> for SsoStringN<N> {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for c in iter {
+            self.push(c);
+        }
+    }
+}
+
+impl<'a, const N: usize> Extend<&'a str> for SsoStringN<N> {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for s in iter {
+            self.push_str(s);
+        }
+    }
+}
+
+impl<const N: usize> Extend<String> for SsoStringN<N> {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for s in iter {
+            self.push_str(&s);
+        }
+    }
+}
+
+impl<const N: usize> core::ops::AddAssign<&str> for SsoStringN<N> {
+    #[inline]
+    fn add_assign(&mut self, rhs: &str) {
+        self.push_str(rhs);
+    }
+}
+
+impl<const N: usize> core::ops::Add<&str> for SsoStringN<N> {
+    type Output = Self;
+
+    /// Appends to `self` in place, so an existing heap buffer is reused rather than cloned.
+    #[inline]
+    fn add(mut self, rhs: &str) -> Self::Output {
+        self.push_str(rhs);
+        self
+    }
+}
+
+/// Closes the gap left by `retain_ascii` when it's dropped, including when the predicate panics:
+/// the unvisited tail is moved down behind the kept bytes. Only ASCII bytes are ever removed, so
+/// the result is valid UTF-8 at any point.
+struct RetainGuard<'a, const N: usize> {
+    string: &'a mut SsoStringN<N>,
+    len: usize,
+    read: usize,
+    kept: usize,
+}
+
+impl<const N: usize> Drop for RetainGuard<'_, N> {
+    fn drop(&mut self) {
+        // Already owned, so this doesn't copy
+        let ptr = self.string.as_mut_ptr();
+        unsafe { ptr.add(self.kept).copy_from(ptr.add(self.read), self.len - self.read) };
+        self.string.update_len(self.kept + self.len - self.read);
+    }
+}
+
+/// Draining iterator returned by `SsoString::drain`.
+pub struct Drain<'a, const N: usize = DEFAULT_INLINE_CAPACITY> {
+    string: *mut SsoStringN<N>,
+    start: usize,
+    end: usize,
+    iter: str::Chars<'a>,
+}
+
+impl<const N: usize> Drain<'_, N> {
+    /// The remaining, not yet yielded part of the drained range.
+    pub fn as_str(&self) -> &str {
+        self.iter.as_str()
+    }
+}
+
+impl<const N: usize> fmt::Debug for Drain<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain").field(&self.as_str()).finish()
+    }
+}
+
+impl<const N: usize> Iterator for Drain<'_, N> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<const N: usize> DoubleEndedIterator for Drain<'_, N> {
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        self.iter.next_back()
+    }
+}
+
+impl<const N: usize> FusedIterator for Drain<'_, N> {}
+
+impl<const N: usize> Drop for Drain<'_, N> {
+    fn drop(&mut self) {
+        unsafe {
+            let string = &mut *self.string;
+            let len = string.len();
+            let ptr = string.as_mut_ptr();
+            ptr.add(self.start).copy_from(ptr.add(self.end), len - self.end);
+            string.update_len(len - (self.end - self.start));
+        }
+    }
+}
+
+/// Owning iterator over the chars of a string, returned by `SsoString::into_iter`.
+#[derive(Clone)]
+pub struct IntoChars<const N: usize = DEFAULT_INLINE_CAPACITY> {
+    string: SsoStringN<N>,
+    front: usize,
+    back: usize,
+}
+
+impl<const N: usize> IntoChars<N> {
+    /// The remaining, not yet yielded chars.
+    pub fn as_str(&self) -> &str {
+        // `front` and `back` only ever move by whole chars
+        unsafe { self.string.as_str().get_unchecked(self.front..self.back) }
+    }
+}
+
+impl<const N: usize> fmt::Debug for IntoChars<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoChars").field(&self.as_str()).finish()
+    }
+}
+
+impl<const N: usize> Iterator for IntoChars<N> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next()?;
+        self.front += c.len_utf8();
+        Some(c)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let bytes = self.back - self.front;
+        (bytes.div_ceil(4), Some(bytes))
+    }
+}
+
+impl<const N: usize> DoubleEndedIterator for IntoChars<N> {
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        self.back -= c.len_utf8();
+        Some(c)
+    }
+}
+
+impl<const N: usize> FusedIterator for IntoChars<N> {}
+
+impl<const N: usize> IntoIterator for SsoStringN<N> {
+    type Item = char;
+    type IntoIter = IntoChars<N>;
+
+    fn into_iter(self) -> IntoChars<N> {
+        let back = self.len();
+        IntoChars { string: self, front: 0, back }
+    }
+}
+
+impl<'a, const N: usize> IntoIterator for &'a SsoStringN<N> {
+    type Item = char;
+    type IntoIter = str::Chars<'a>;
+
+    fn into_iter(self) -> str::Chars<'a> {
+        self.chars()
+    }
+}
+
 impl SsoStringable for String {
     fn to_sso_string(&self) -> SsoString {
         SsoString::from(self)
@@ -770,10 +2588,60 @@ impl SsoStringable for String {
 
 impl SsoStringable for &str {
     fn to_sso_string(&self) -> SsoString {
-        SsoString::from(self)
+        SsoString::from(*self)
     }
 }
 
+/// Counts how many strings in `strings` are (inline, heap, static). Useful for checking
+/// whether a workload actually benefits from the inline representation.
+pub fn storage_histogram(strings: &[SsoString]) -> (usize, usize, usize) {
+    let mut counts = (0, 0, 0);
+    for s in strings {
+        match s.representation() {
+            Representation::Inline => counts.0 += 1,
+            Representation::Heap => counts.1 += 1,
+            Representation::Static => counts.2 += 1,
+        }
+    }
+    counts
+}
+
+const WORD_SIZE: usize = mem::size_of::<usize>();
+const LO_BYTES: usize = usize::from_ne_bytes([0x01; WORD_SIZE]);
+const HI_BYTES: usize = usize::from_ne_bytes([0x80; WORD_SIZE]);
+
+/// True if any byte of `word` is zero.
+#[inline]
+const fn has_zero_byte(word: usize) -> bool {
+    word.wrapping_sub(LO_BYTES) & !word & HI_BYTES != 0
+}
+
+/// Skips whole words that can't contain `needle`, then finds it in the remaining bytes.
+fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    let repeated = LO_BYTES * needle as usize;
+    let mut offset = 0;
+    for chunk in haystack.chunks_exact(WORD_SIZE) {
+        let word = usize::from_ne_bytes(chunk.try_into().unwrap());
+        if has_zero_byte(word ^ repeated) {
+            break;
+        }
+        offset += WORD_SIZE;
+    }
+    haystack[offset..].iter().position(|&b| b == needle).map(|i| offset + i)
+}
+
+fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    let repeated = LO_BYTES * needle as usize;
+    let mut end = haystack.len();
+    for chunk in haystack.rchunks_exact(WORD_SIZE) {
+        let word = usize::from_ne_bytes(chunk.try_into().unwrap());
+        if has_zero_byte(word ^ repeated) {
+            break;
+        }
+        end -= WORD_SIZE;
+    }
+    haystack[..end].iter().rposition(|&b| b == needle)
+}
 
 #[cfg(test)]
 mod private_tests {
@@ -792,7 +2660,7 @@ mod private_tests {
         assert!(!s_static_heap.is_inlined());
         assert_eq!(s_static_heap.len(), static_str_long_literal.len());
         assert_eq!(s_static_heap.as_str(), static_str_long_literal);
-        assert_eq!(s_static_heap.metadata().is_static(), true);
+        assert!(s_static_heap.metadata().is_static());
     }
 
     #[test]
@@ -801,11 +2669,11 @@ mod private_tests {
         let to_push_literal = "_plus_this";
         let mut s = SsoString::from_static(main_static_literal);
         assert!(!s.is_inlined());
-        assert_eq!(s.metadata().is_static(), true, "Initially static heap");
+        assert!(s.metadata().is_static(), "Initially static heap");
 
         s.push_str(to_push_literal);
         assert!(!s.is_inlined(), "Should remain on heap");
-        assert_eq!(s.metadata().is_static(), false, "Should become non-static after push_str");
+        assert!(!s.metadata().is_static(), "Should become non-static after push_str");
         let expected_str_obj = String::from(main_static_literal) + to_push_literal;
         assert_eq!(s.as_str(), expected_str_obj.as_str());
         assert_eq!(s.len(), expected_str_obj.len());
@@ -828,17 +2696,87 @@ mod private_tests {
         assert!(!s1_heap.metadata().is_static());
         assert!(!s2_heap.metadata().is_static());
         assert_eq!(s1_heap.as_str(), s2_heap.as_str());
-        assert_ne!(s1_heap.pointer, s2_heap.pointer, "Heap clone should have different pointer");
+        assert_ne!(s1_heap.heap().pointer, s2_heap.heap().pointer, "Heap clone should have different pointer");
 
         let static_data_literal = "clone_me_static_heap_long_string";
         let s1_static_heap = SsoString::from_static(static_data_literal);
         let s2_static_heap = s1_static_heap.clone();
         assert!(!s2_static_heap.is_inlined());
         assert_eq!(s1_static_heap.as_str(), s2_static_heap.as_str());
-        assert_eq!(s1_static_heap.metadata().is_static(), true);
-        assert_eq!(s2_static_heap.metadata().is_static(), true, "Clone of static string should also be marked static initially");
-        if s1_static_heap.pointer != 0 as *mut u8 {
-             assert_eq!(s1_static_heap.pointer, s2_static_heap.pointer, "Clone of static heap string should share pointer until CoW");
+        assert!(s1_static_heap.metadata().is_static());
+        assert!(s2_static_heap.metadata().is_static(), "Clone of static string should also be marked static initially");
+        if !s1_static_heap.heap().pointer.is_null() {
+             assert_eq!(s1_static_heap.heap().pointer, s2_static_heap.heap().pointer, "Clone of static heap string should share pointer until CoW");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_growth_near_usize_max_does_not_wrap() {
+        let half = usize::MAX / 2;
+        // `(half * 3) >> 1` used to wrap around to a capacity smaller than the length
+        let grown = SsoString::grow_capacity(half, half + 1);
+        assert!(grown > half);
+        assert_eq!(SsoString::try_heap_layout(grown), Err(TryReserveError::CapacityOverflow));
+
+        let max = SsoString::MAX_CAPACITY;
+        assert_eq!(SsoString::grow_capacity(max, max), max);
+        assert_eq!(SsoString::grow_capacity(max - 1, max), max);
+        assert_eq!(SsoString::grow_capacity(max / 3 * 2, 1), max);
+
+        let mut s = SsoString::from("a heap allocated string that is long enough");
+        assert_eq!(s.try_grow_to(half), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(s, "a heap allocated string that is long enough");
+    }
+
+    #[test]
+    fn test_force_heap_relocation_never_undersizes() {
+        let literal = "a static string relocated with a capacity below its length";
+        let mut s = SsoString::from_static(literal);
+        assert!(s.force_heap_relocation(4));
+        assert!(s.is_heap_allocated());
+        assert_eq!(s.capacity(), literal.len());
+        assert_eq!(s, literal);
+
+        let mut inline = SsoString::from("inline");
+        assert!(inline.force_heap_relocation(0));
+        assert_eq!(inline.capacity(), 6);
+        assert_eq!(inline, "inline");
+    }
+
+    #[test]
+    fn test_swap_points_inline_data_at_own_buffer() {
+        let mut inline = SsoString::from("inline");
+        let mut heap = SsoString::from("a heap string, too long to be stored inline");
+        inline.swap(&mut heap);
+        assert!(heap.is_inlined());
+        assert_eq!(heap.as_ptr(), heap.inline_ptr());
+        assert_eq!(heap.inline_ptr(), (&heap as *const SsoString as *const u8).wrapping_add(1));
+        assert_eq!(heap, "inline");
+    }
+
+    #[test]
+    fn test_adopt_buffer_copies_past_max_capacity() {
+        let text = "a heap string that is long enough to leave the inline buffer";
+        let buffer = || {
+            let mut bytes = Vec::with_capacity(100);
+            bytes.extend_from_slice(text.as_bytes());
+            bytes
+        };
+
+        let bytes = buffer();
+        let ptr = bytes.as_ptr();
+        let copied = unsafe { SsoString::adopt_buffer(bytes, 64) };
+        assert_ne!(copied.as_ptr(), ptr);
+        assert_eq!(copied.capacity(), text.len());
+        assert_eq!(copied, text);
+        copied.validate();
+
+        let bytes = buffer();
+        let ptr = bytes.as_ptr();
+        let adopted = unsafe { SsoString::adopt_buffer(bytes, 100) };
+        assert_eq!(adopted.as_ptr(), ptr);
+        assert_eq!(adopted.capacity(), 100);
+        assert_eq!(adopted, text);
+    }
+}
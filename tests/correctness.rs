@@ -1,8 +1,9 @@
-use sso_string::{SsoString, SsosPrecond};
+use sso_string::{format_sso, storage_histogram, Representation, SsoString, SsoStringN, SsosPrecond, TryReserveError, DEFAULT_INLINE_CAPACITY};
 
 #[cfg(test)]
 mod correctness_tests {
     use super::*;
+    #[cfg(feature = "std")]
     use std::collections::HashMap;
 
     #[test]
@@ -125,9 +126,6 @@ mod correctness_tests {
         let world_literal = "world";
         let long_str_1_literal = "long_string_example_for_testing_equality_and_hash";
         let long_str_2_literal = "another_long_string_for_testing_different_content";
-        
-        let inline_val_literal = "inline_val";
-        let heap_val_literal = "heap_val";
 
         let s1_inline = SsoString::from(hello_literal);
         let s2_inline = SsoString::from(hello_literal);
@@ -143,13 +141,19 @@ mod correctness_tests {
         assert_ne!(s1_heap, s3_heap);
         assert_ne!(s1_inline, s1_heap);
 
-        let mut map = HashMap::new();
-        map.insert(s1_inline.clone(), inline_val_literal);
-        map.insert(s1_heap.clone(), heap_val_literal);
+        #[cfg(feature = "std")]
+        {
+            let inline_val_literal = "inline_val";
+            let heap_val_literal = "heap_val";
 
-        assert_eq!(map.get(&s2_inline), Some(&inline_val_literal));
-        assert_eq!(map.get(&s2_heap), Some(&heap_val_literal));
-        assert_eq!(map.get(&s3_inline), None);
+            let mut map = HashMap::new();
+            map.insert(s1_inline.clone(), inline_val_literal);
+            map.insert(s1_heap.clone(), heap_val_literal);
+
+            assert_eq!(map.get(&s2_inline), Some(&inline_val_literal));
+            assert_eq!(map.get(&s2_heap), Some(&heap_val_literal));
+            assert_eq!(map.get(&s3_inline), None);
+        }
 
         assert!(s1_inline < s3_inline);
         assert!(s3_heap < s1_heap);
@@ -181,7 +185,7 @@ mod correctness_tests {
     fn test_reserve_inline() {
         let initial_literal = "small";
         let to_push_literal = "1234567890";
-        let reserve_amount = 10;
+        let reserve_amount = 30;
         let expected_final_literal = "small1234567890";
 
         let mut s = SsoString::from(initial_literal);
@@ -429,16 +433,36 @@ mod correctness_tests {
     #[test]
     fn test_with_capacity_zero() {
         let s = SsoString::with_capacity(0);
-        assert!(!s.is_inlined());
+        assert!(s.is_inlined());
         assert!(!s.is_static());
         assert_eq!(s.len(), 0);
-        assert_eq!(s.capacity(), 0); // Capacity is 0
+        assert_eq!(s.capacity(), SsoString::new().capacity());
         assert_eq!(s.as_str(), "");
     }
 
+    #[test]
+    fn test_with_capacity_inlines_small_requests() {
+        let inline_cap = SsoString::new().capacity();
+        let mut s = SsoString::with_capacity(10);
+        assert!(s.is_inlined());
+        s.push_str("ten bytes!");
+        assert!(s.is_inlined());
+
+        assert!(SsoString::with_capacity(inline_cap).is_inlined());
+        let heap = SsoString::with_capacity(inline_cap + 1);
+        assert!(!heap.is_inlined());
+        assert_eq!(heap.capacity(), inline_cap + 1);
+        assert!(SsoStringN::<40>::with_capacity(40).is_inlined());
+    }
+
+    /// A heap string that never allocated, now that `with_capacity(0)` stays inline.
+    fn zero_capacity_heap() -> SsoString {
+        unsafe { SsoString::from_raw_parts(std::ptr::NonNull::dangling().as_ptr(), 0, 0) }
+    }
+
     #[test]
     fn test_push_str_to_zero_capacity_heap() {
-        let mut s = SsoString::with_capacity(0);
+        let mut s = zero_capacity_heap();
         assert_eq!(s.capacity(), 0);
         s.push_str("hello");
         assert!(!s.is_inlined());
@@ -466,4 +490,2251 @@ mod correctness_tests {
         let sso_string4 = SsoString::from(s4.clone());
         assert_eq!(sso_string4, s4);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_reversed_bytes() {
+        let inline = SsoString::from("héllo");
+        let expected: Vec<u8> = inline.as_bytes().iter().rev().copied().collect();
+        assert_eq!(inline.reversed_bytes(), expected);
+
+        let heap = SsoString::from("a string long enough to live on the heap");
+        let expected: Vec<u8> = heap.as_bytes().iter().rev().copied().collect();
+        assert_eq!(heap.reversed_bytes(), expected);
+
+        assert!(SsoString::new().reversed_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        let inline = SsoString::from("shown");
+        assert_eq!(format!("{}", inline), "shown");
+
+        let heap = SsoString::from("a string long enough to live on the heap");
+        assert_eq!(format!("{}", heap), heap.as_str());
+        assert_eq!(heap.to_string(), heap.as_str());
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut inline = SsoString::from("hello world");
+        inline.truncate(5);
+        assert!(inline.is_inlined());
+        assert_eq!(inline.as_str(), "hello");
+        inline.truncate(50);
+        assert_eq!(inline.as_str(), "hello");
+
+        let mut heap = SsoString::from("a string long enough to live on the heap");
+        heap.truncate(8);
+        assert_eq!(heap.as_str(), "a string");
+
+        let literal = "a static string long enough to skip inlining";
+        let mut stat = SsoString::from_static(literal);
+        stat.truncate(15);
+        assert_eq!(stat.as_str(), "a static string");
+        assert_eq!(literal.len(), 44);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_truncate_non_boundary_panics() {
+        let mut s = SsoString::from("héllo");
+        s.truncate(2);
+    }
+
+    #[test]
+    fn test_truncate_lossy() {
+        // 'é' occupies bytes 1..3, so index 2 is mid-codepoint
+        let mut s = SsoString::from("héllo");
+        s.truncate_lossy(2);
+        assert_eq!(s.as_str(), "h");
+        assert!(s.as_str().is_char_boundary(s.len()));
+
+        let mut heap = SsoString::from("日本語のテキストはマルチバイトです");
+        heap.truncate_lossy(10);
+        assert_eq!(heap.as_str(), "日本語");
+
+        let mut unchanged = SsoString::from("short");
+        unchanged.truncate_lossy(100);
+        assert_eq!(unchanged.as_str(), "short");
+    }
+
+    #[test]
+    fn test_fmt_write() {
+        use std::fmt::Write;
+
+        let mut s = SsoString::new();
+        let (num, tag) = (12, 'X');
+        write!(s, "{}-{}", num, tag).unwrap();
+        assert!(s.is_inlined());
+        assert_eq!(s.as_str(), "12-X");
+
+        write!(s, " and then some text to push it onto the heap").unwrap();
+        assert!(!s.is_inlined());
+        assert_eq!(s.as_str(), "12-X and then some text to push it onto the heap");
+
+        s.write_char('!').unwrap();
+        assert!(s.ends_with("heap!"));
+    }
+
+    #[test]
+    fn test_is_whitespace() {
+        assert!(SsoString::from("    ").is_whitespace());
+        assert!(SsoString::from(" \t\n\u{3000}  \r\n                     ").is_whitespace());
+        assert!(!SsoString::from("  a  ").is_whitespace());
+        assert!(SsoString::from("").is_whitespace());
+        assert!(SsoString::new().is_whitespace());
+    }
+
+    #[test]
+    fn test_from_iterator() {
+        let short: SsoString = "hello".chars().collect();
+        assert!(short.is_inlined());
+        assert_eq!(short, "hello");
+
+        let long: SsoString = std::iter::repeat_n('a', 40).collect();
+        assert!(!long.is_inlined());
+        assert_eq!(long.as_str(), "a".repeat(40));
+
+        let pieces: SsoString = vec!["ab", "cd", "ef"].into_iter().collect();
+        assert!(pieces.is_inlined());
+        assert_eq!(pieces, "abcdef");
+
+        let owned: SsoString = vec![String::from("a long owned piece, "), String::from("and another one")]
+            .into_iter()
+            .collect();
+        assert!(!owned.is_inlined());
+        assert_eq!(owned, "a long owned piece, and another one");
+
+        let empty: SsoString = std::iter::empty::<char>().collect();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_prefix_compress() {
+        let s = SsoString::from("prefix/shared");
+        assert_eq!(s.prefix_compress("prefix/shared"), (13, ""));
+
+        let s = SsoString::from("/usr/local/share/applications");
+        assert_eq!(s.prefix_compress("/usr/local/bin"), (11, "share/applications"));
+
+        let s = SsoString::from("abc");
+        assert_eq!(s.prefix_compress("xyz"), (0, "abc"));
+
+        // "é" and "è" share their first UTF-8 byte; the split must not land inside it
+        let s = SsoString::from("café");
+        assert_eq!(s.prefix_compress("cafè"), (3, "é"));
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut s = SsoString::from("ab");
+        s.extend(['c', 'd']);
+        assert!(s.is_inlined());
+        assert_eq!(s, "abcd");
+
+        s.extend(vec!["ef", "gh"]);
+        assert!(s.is_inlined());
+        assert_eq!(s, "abcdefgh");
+
+        s.extend(vec![String::from("-and-now-long-enough-for-heap")]);
+        assert!(!s.is_inlined());
+        assert_eq!(s, "abcdefgh-and-now-long-enough-for-heap");
+
+        let mut heap = SsoString::from("a string long enough to live on the heap");
+        heap.extend(std::iter::repeat_n('x', 100));
+        assert_eq!(heap.len(), 40 + 100);
+        assert!(heap.ends_with(&"x".repeat(100)));
+    }
+
+    #[test]
+    fn test_prefix_expand_round_trip() {
+        let base = "/usr/local/bin";
+        for original in ["/usr/local/share/applications", "/usr/lib", "relative/path", "/usr/local/bin", "/usr/café"] {
+            let s = SsoString::from(original);
+            let (shared, suffix) = s.prefix_compress(base);
+            let expanded = SsoString::prefix_expand(base, shared, suffix);
+            assert_eq!(expanded, s);
+            assert_eq!(expanded.is_inlined(), original.len() <= 23);
+        }
+    }
+
+    #[test]
+    fn test_add_and_add_assign() {
+        let mut s = SsoString::from("abc");
+        s += "def";
+        assert!(s.is_inlined());
+        assert_eq!(s, "abcdef");
+
+        let s = s + " and some more to spill over";
+        assert!(!s.is_inlined());
+        assert_eq!(s, "abcdef and some more to spill over");
+
+        let heap = SsoString::from("a string long enough to live on the heap");
+        let ptr = heap.as_ptr();
+        let heap = heap + "";
+        assert_eq!(heap.as_ptr(), ptr, "Add should reuse the existing buffer");
+    }
+
+    #[test]
+    fn test_from_string_reuses_allocation() {
+        let long = String::from("a string long enough to live on the heap");
+        let ptr = long.as_ptr();
+        let sso = SsoString::from(long);
+        assert!(!sso.is_inlined());
+        assert!(!sso.is_static());
+        assert_eq!(sso.as_ptr(), ptr, "Long strings should keep the String's buffer");
+        assert_eq!(sso, "a string long enough to live on the heap");
+
+        let short = SsoString::from(String::from("short"));
+        assert!(short.is_inlined());
+        assert_eq!(short, "short");
+
+        let borrowed = String::from("borrowed");
+        assert_eq!(SsoString::from(&borrowed), "borrowed");
+
+        let slice = "double reference";
+        assert_eq!(SsoString::from(&slice), slice);
+        assert_eq!(SsoString::from(std::borrow::Cow::Borrowed("cow")), "cow");
+        let owned = String::from("an owned cow that is long enough for the heap");
+        let ptr = owned.as_ptr();
+        let cow = SsoString::from(std::borrow::Cow::Owned(owned));
+        assert_eq!(cow.as_ptr(), ptr, "Owned Cows should keep the String's buffer");
+    }
+
+    #[test]
+    fn test_storage_histogram() {
+        let strings = vec![
+            SsoString::from("a"),
+            SsoString::from("bb"),
+            SsoString::from("ccc"),
+            SsoString::from("a string long enough to live on the heap"),
+            SsoString::from_static("static"),
+            SsoString::from_static("a static string long enough for the heap"),
+        ];
+        assert_eq!(storage_histogram(&strings), (3, 1, 2));
+        assert_eq!(storage_histogram(&[]), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_into_bytes() {
+        let heap = SsoString::from("a string long enough to live on the heap");
+        let ptr = heap.as_ptr();
+        let bytes = heap.into_bytes();
+        assert_eq!(bytes.as_ptr(), ptr, "Heap buffer should be handed over without copying");
+        assert_eq!(bytes, b"a string long enough to live on the heap");
+
+        let inline = SsoString::from("inline");
+        assert_eq!(inline.into_bytes(), b"inline");
+
+        let stat = SsoString::from_static("a static string long enough for the heap");
+        assert_eq!(stat.into_bytes(), b"a static string long enough for the heap");
+    }
+
+    #[test]
+    fn test_into_string() {
+        let heap = SsoString::from("a string long enough to live on the heap");
+        let ptr = heap.as_ptr();
+        let string = heap.into_string();
+        assert_eq!(string.as_ptr(), ptr, "Heap buffer should be handed over without copying");
+        assert_eq!(string, "a string long enough to live on the heap");
+
+        let heap = SsoString::from(String::from("another string long enough for the heap"));
+        let ptr = heap.as_ptr();
+        let string: String = heap.into();
+        assert_eq!(string.as_ptr(), ptr);
+
+        let inline: String = SsoString::from("inline").into();
+        assert_eq!(inline, "inline");
+        assert_eq!(SsoString::from_static("static").into_string(), "static");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_borrow_str_lookup() {
+        let mut map = HashMap::new();
+        map.insert(SsoString::from("key"), 1);
+        map.insert(SsoString::from("a key long enough to live on the heap"), 2);
+        map.insert(SsoString::from_static("static key"), 3);
+
+        assert_eq!(map.get("key"), Some(&1));
+        assert_eq!(map.get("a key long enough to live on the heap"), Some(&2));
+        assert_eq!(map.get("static key"), Some(&3));
+        assert_eq!(map.get("missing"), None);
+    }
+
+    #[test]
+    fn test_hash_matches_std_string() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        for literal in ["", "short", "a string long enough to live on the heap"] {
+            let expected = hash_of(&String::from(literal));
+            assert_eq!(hash_of(&SsoString::from(literal)), expected);
+            assert_eq!(hash_of(&SsoString::from_static(literal)), expected);
+            assert_eq!(hash_of(literal), expected);
+        }
+    }
+
+    #[test]
+    fn test_from_utf8_mutate_and_drop() {
+        // The Vec's buffer is adopted as-is, so growing and dropping it must use the layout it was allocated with.
+        let bytes = b"a vec long enough to be adopted as a heap buffer".to_vec();
+        let mut s = SsoString::from_utf8(bytes).unwrap();
+        s.push_str(" and then grown past its original capacity");
+        s.reserve(64);
+        assert_eq!(s, "a vec long enough to be adopted as a heap buffer and then grown past its original capacity");
+        drop(s);
+
+        let mut short = SsoString::from_utf8(b"tiny".to_vec()).unwrap();
+        assert!(short.is_inlined());
+        short.push_str("!");
+        assert_eq!(short, "tiny!");
+
+        assert!(SsoString::from_utf8(vec![0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn test_inline_capacity_matches_struct_size() {
+        let inline_capacity = std::mem::size_of::<SsoString>() - 1;
+        assert_eq!(std::mem::size_of::<SsoString>(), 3 * std::mem::size_of::<usize>());
+
+        // Covers the 12-23 byte range that only fits inline on 64-bit targets
+        let source = "abcdefghijklmnopqrstuvwxyz0123";
+        for len in 0..=source.len() {
+            let literal = &source[..len];
+            let s = SsoString::from(literal);
+            assert_eq!(s.is_inlined(), len <= inline_capacity, "len {}", len);
+            assert_eq!(s.as_str(), literal);
+
+            let mut pushed = SsoString::new();
+            pushed.push_str(literal);
+            assert_eq!(pushed.is_inlined(), len <= inline_capacity, "len {}", len);
+            assert_eq!(pushed.as_str(), literal);
+        }
+    }
+
+    #[test]
+    fn test_zero_capacity_heap_paths() {
+        let empty = zero_capacity_heap();
+        let cloned = empty.clone();
+        assert_eq!(cloned.as_str(), "");
+        assert_eq!(cloned.capacity(), 0);
+        drop(empty);
+        drop(cloned);
+
+        let mut reserved = zero_capacity_heap();
+        reserved.reserve(8);
+        assert!(reserved.capacity() >= 8);
+        reserved.push_str("reserved");
+        assert_eq!(reserved, "reserved");
+
+        let mut assumed = zero_capacity_heap();
+        unsafe { assumed.push_str_assume::<{SsosPrecond::Heap.into_param()}>("pushed onto an empty buffer") };
+        assert_eq!(assumed, "pushed onto an empty buffer");
+    }
+
+    #[test]
+    fn test_as_bytes_mut_copies_static() {
+        static LONG: &str = "a static string long enough to skip inlining";
+        let mut s = SsoString::from_static(LONG);
+        s.as_bytes_mut()[0] = b'A';
+        assert!(!s.is_static());
+        assert_eq!(s, "A static string long enough to skip inlining");
+        assert_eq!(LONG, "a static string long enough to skip inlining");
+        assert_ne!(s.as_ptr(), LONG.as_ptr());
+
+        static SHORT: &str = "short static";
+        let mut s = SsoString::from_static(SHORT);
+        unsafe { *s.as_mut_ptr() = b'S' };
+        assert!(s.is_inlined());
+        assert_eq!(s, "Short static");
+        assert_eq!(SHORT, "short static");
+    }
+
+    #[test]
+    fn test_reserve_noop_when_capacity_suffices() {
+        let mut inline = SsoString::from("small");
+        inline.reserve(10);
+        assert!(inline.is_inlined(), "Inline capacity already covers the request");
+
+        let mut heap = SsoString::from("this is a longer string on the heap");
+        heap.reserve(40);
+        let cap = heap.capacity();
+        let ptr = heap.as_ptr();
+        heap.reserve(10);
+        heap.reserve(40);
+        assert_eq!(heap.capacity(), cap);
+        assert_eq!(heap.as_ptr(), ptr);
+        assert!(heap.capacity() >= heap.len() + 40);
+
+        let mut stat = SsoString::from_static("a static string long enough for the heap");
+        stat.reserve(0);
+        assert!(stat.is_static());
+        stat.reserve(1);
+        assert!(!stat.is_static());
+        assert!(stat.capacity() > stat.len());
+
+        let literal = "a static string long enough for the heap, and then some more text";
+        for reserve in [SsoString::reserve, SsoString::reserve_exact, |s: &mut SsoString, n| s.try_reserve(n).unwrap()] {
+            let mut truncated = SsoString::from_static(literal);
+            truncated.truncate(30);
+            assert!(truncated.is_static());
+            reserve(&mut truncated, 20);
+            assert!(!truncated.is_static());
+            assert!(truncated.capacity_remaining() >= 20);
+            assert_eq!(truncated, &literal[..30]);
+        }
+    }
+
+    #[test]
+    fn test_reserve_exact() {
+        let mut inline = SsoString::from("small");
+        inline.reserve_exact(10);
+        assert!(inline.is_inlined());
+        inline.reserve_exact(30);
+        assert!(!inline.is_inlined());
+        assert_eq!(inline.capacity(), 35);
+        assert_eq!(inline, "small");
+
+        let mut heap = SsoString::from("this is a longer string on the heap");
+        let len = heap.len();
+        heap.reserve_exact(7);
+        assert_eq!(heap.capacity(), len + 7);
+        heap.reserve_exact(3);
+        assert_eq!(heap.capacity(), len + 7);
+        heap.push_str("1234567");
+        assert_eq!(heap.capacity(), len + 7);
+        assert_eq!(heap, "this is a longer string on the heap1234567");
+
+        let mut stat = SsoString::from_static("a static string long enough for the heap");
+        stat.reserve_exact(4);
+        assert!(!stat.is_static());
+        assert_eq!(stat.capacity(), stat.len() + 4);
+    }
+
+    #[test]
+    fn test_try_reserve() {
+        let mut s = SsoString::from("small");
+        assert_eq!(s.try_reserve(10), Ok(()));
+        assert!(s.is_inlined());
+        assert_eq!(s.try_reserve(100), Ok(()));
+        assert!(s.capacity() >= 105);
+        assert_eq!(s, "small");
+
+        assert_eq!(s.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(s.try_reserve(usize::MAX >> 8), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(s, "small", "A failed reservation leaves the string untouched");
+
+        let mut stat = SsoString::from_static("a static string long enough for the heap");
+        assert_eq!(stat.try_reserve(usize::MAX - 10), Err(TryReserveError::CapacityOverflow));
+        assert!(stat.is_static());
+    }
+
+    #[test]
+    #[cfg(all(target_pointer_width = "64", not(miri)))]
+    fn test_try_reserve_alloc_failure() {
+        // Representable, but far more memory than any allocator will hand out
+        let mut s = SsoString::from("a string long enough to live on the heap");
+        let result = s.try_reserve(1 << 54);
+        assert!(matches!(result, Err(TryReserveError::AllocError { .. })), "{:?}", result);
+        assert_eq!(s, "a string long enough to live on the heap");
+    }
+
+    #[test]
+    fn test_from_utf16() {
+        let ascii: Vec<u16> = "hello".encode_utf16().collect();
+        let s = SsoString::from_utf16(&ascii).unwrap();
+        assert!(s.is_inlined());
+        assert_eq!(s, "hello");
+
+        let mixed_literal = "BMP: ü€ 中文, astral: 𝄞😀 and enough text for the heap";
+        let mixed: Vec<u16> = mixed_literal.encode_utf16().collect();
+        let s = SsoString::from_utf16(&mixed).unwrap();
+        assert!(!s.is_inlined());
+        assert_eq!(s, mixed_literal);
+        assert_eq!(s.encode_utf16().collect::<Vec<u16>>(), mixed);
+
+        let lone_surrogate = [0x0068, 0xD834, 0x0069];
+        assert!(SsoString::from_utf16(&lone_surrogate).is_err());
+        assert_eq!(SsoString::from_utf16_lossy(&lone_surrogate), "h\u{FFFD}i");
+        assert_eq!(SsoString::from_utf16_lossy(&mixed), mixed_literal);
+    }
+
+    #[test]
+    fn test_assume_ascii() {
+        let mut inline = SsoString::from("abc");
+        unsafe { inline.push_str_assume::<{SsosPrecond::Ascii.into_param()}>("def") };
+        assert!(inline.is_inlined());
+        assert_eq!(unsafe { inline.as_str_assume::<{SsosPrecond::Ascii.into_param()}>() }, "abcdef");
+
+        unsafe { inline.push_str_assume::<{SsosPrecond::Ascii.into_param()}>("-now-spilling-onto-the-heap") };
+        assert!(!inline.is_inlined());
+        assert_eq!(unsafe { inline.as_str_assume::<{SsosPrecond::Ascii.into_param()}>() }, "abcdef-now-spilling-onto-the-heap");
+
+        let mut stat = SsoString::from_static("static ascii");
+        unsafe { stat.push_str_assume::<{SsosPrecond::Ascii.into_param()}>("!") };
+        assert!(!stat.is_static());
+        assert_eq!(stat, "static ascii!");
+    }
+
+    #[test]
+    fn test_make_ascii_case() {
+        let mut inline = SsoString::from("Hello, Wörld");
+        inline.make_ascii_uppercase();
+        assert!(inline.is_inlined());
+        assert_eq!(inline, "HELLO, WöRLD");
+        inline.make_ascii_lowercase();
+        assert_eq!(inline, "hello, wörld");
+
+        let mut heap = SsoString::from("Mixed Case Content On The Heap");
+        heap.make_ascii_lowercase();
+        assert_eq!(heap, "mixed case content on the heap");
+
+        static LITERAL: &str = "Static Content Long Enough For The Heap";
+        let mut stat = SsoString::from_static(LITERAL);
+        stat.make_ascii_uppercase();
+        assert_eq!(stat, "STATIC CONTENT LONG ENOUGH FOR THE HEAP");
+        assert_eq!(LITERAL, "Static Content Long Enough For The Heap");
+    }
+
+    #[test]
+    fn test_to_ascii_case() {
+        let original = SsoString::from("Key-Name");
+        let upper: SsoString = original.to_ascii_uppercase();
+        assert!(upper.is_inlined());
+        assert_eq!(upper, "KEY-NAME");
+        assert_eq!(original.to_ascii_lowercase(), "key-name");
+        assert_eq!(original, "Key-Name");
+
+        let stat = SsoString::from_static("A Static Key Long Enough For The Heap");
+        assert_eq!(stat.to_ascii_lowercase(), "a static key long enough for the heap");
+        assert!(stat.is_static());
+        assert_eq!(stat, "A Static Key Long Enough For The Heap");
+    }
+
+    #[test]
+    fn test_unicode_case() {
+        let s = SsoString::from("straße");
+        let upper = s.to_uppercase();
+        assert!(upper.is_inlined());
+        assert_eq!(upper, "STRASSE");
+
+        // 22 bytes inline, but each 'ΐ' uppercases to three chars (6 bytes) so the result no longer fits
+        let literal = "ΐΐΐΐΐΐΐΐΐΐΐ";
+        let s = SsoString::from(literal);
+        assert!(s.is_inlined());
+        let upper = s.to_uppercase();
+        assert!(!upper.is_inlined());
+        assert_eq!(upper, literal.to_uppercase());
+        assert_eq!(upper.len(), 66);
+
+        assert_eq!(SsoString::from("ÀÉÎÕÜ Ωmega").to_lowercase(), "àéîõü ωmega");
+        assert_eq!(SsoString::from("ὈΔΥΣΣΕΎΣ").to_lowercase(), "ὀδυσσεύς");
+        for literal in ["İstanbul", "ǅ digraph", "ΑΣ ΣΑ", "mixed Case ascii content on the heap"] {
+            assert_eq!(SsoString::from(literal).to_lowercase(), literal.to_lowercase());
+            assert_eq!(SsoString::from(literal).to_uppercase(), literal.to_uppercase());
+        }
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut s = SsoString::from("héllo world");
+        let drained: String = s.drain(..7).collect();
+        assert_eq!(drained, "héllo ");
+        assert!(s.is_inlined());
+        assert_eq!(s, "world");
+
+        let mut heap = SsoString::from("a string long enough to live on the heap");
+        let drained: Vec<char> = heap.drain(2..9).rev().collect();
+        assert_eq!(drained, vec![' ', 'g', 'n', 'i', 'r', 't', 's']);
+        assert_eq!(heap, "a long enough to live on the heap");
+
+        // Partially consumed: the whole range is still removed on drop
+        let mut partial = SsoString::from("0123456789");
+        let mut drain = partial.drain(2..=5);
+        assert_eq!(drain.next(), Some('2'));
+        assert_eq!(drain.as_str(), "345");
+        drop(drain);
+        assert_eq!(partial, "016789");
+
+        static LITERAL: &str = "a static string long enough to skip inlining";
+        let mut stat = SsoString::from_static(LITERAL);
+        assert_eq!(stat.drain(1..).count(), LITERAL.len() - 1);
+        assert!(!stat.is_static());
+        assert_eq!(stat, "a");
+        assert_eq!(LITERAL, "a static string long enough to skip inlining");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_drain_non_boundary_panics() {
+        let mut s = SsoString::from("héllo");
+        s.drain(..2);
+    }
+
+    #[test]
+    fn test_replace_range() {
+        let mut s = SsoString::from("hello world");
+        s.replace_range(0..5, "howdy");
+        assert_eq!(s, "howdy world");
+        s.replace_range(6.., "");
+        assert!(s.is_inlined());
+        assert_eq!(s, "howdy ");
+
+        s.replace_range(..5, "a replacement long enough to leave inline storage");
+        assert!(!s.is_inlined());
+        assert_eq!(s, "a replacement long enough to leave inline storage ");
+
+        s.replace_range(2..13, "swap");
+        assert_eq!(s, "a swap long enough to leave inline storage ");
+
+        let mut multibyte = SsoString::from("naïve café");
+        multibyte.replace_range(2..4, "i");
+        assert_eq!(multibyte, "naive café");
+
+        static LITERAL: &str = "a static string long enough to skip inlining";
+        let mut stat = SsoString::from_static(LITERAL);
+        stat.replace_range(2..8, "shared");
+        assert_eq!(stat, "a shared string long enough to skip inlining");
+        assert_eq!(LITERAL, "a static string long enough to skip inlining");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_replace_range_non_boundary_panics() {
+        let mut s = SsoString::from("naïve");
+        s.replace_range(3.., "");
+    }
+
+    #[test]
+    fn test_index_ranges() {
+        let s = SsoString::from("hello world");
+        assert_eq!(&s[0..5], "hello");
+        assert_eq!(&s[..5], "hello");
+        assert_eq!(&s[6..], "world");
+        assert_eq!(&s[..], "hello world");
+        assert_eq!(&s[0..=4], "hello");
+        assert_eq!(&s[..=4], "hello");
+
+        fn first_three<T: std::ops::Index<std::ops::Range<usize>, Output = str> + ?Sized>(t: &T) -> &str {
+            &t[0..3]
+        }
+        assert_eq!(first_three(&s), "hel");
+
+        static LITERAL: &str = "a static string long enough to skip inlining";
+        let mut stat = SsoString::from_static(LITERAL);
+        stat[2..8].make_ascii_uppercase();
+        assert_eq!(stat, "a STATIC string long enough to skip inlining");
+        assert_eq!(LITERAL, "a static string long enough to skip inlining");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_non_boundary_panics() {
+        let s = SsoString::from("héllo");
+        let _ = &s[..2];
+    }
+
+    #[test]
+    fn test_get_and_get_mut() {
+        let s = SsoString::from("héllo");
+        assert_eq!(s.get(0..1), Some("h"));
+        assert_eq!(s.get(3..), Some("llo"));
+        assert_eq!(s.get(0..2), None, "Splits 'é'");
+        assert_eq!(s.get(..10), None, "Out of bounds");
+
+        let mut heap = SsoString::from("a string long enough to live on the heap");
+        heap.get_mut(2..8).unwrap().make_ascii_uppercase();
+        assert_eq!(heap, "a STRING long enough to live on the heap");
+        assert!(heap.get_mut(100..).is_none());
+
+        static LITERAL: &str = "a static string long enough to skip inlining";
+        let mut stat = SsoString::from_static(LITERAL);
+        stat.get_mut(..1).unwrap().make_ascii_uppercase();
+        assert_eq!(stat, "A static string long enough to skip inlining");
+        assert_eq!(LITERAL, "a static string long enough to skip inlining");
+    }
+
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SsoString>();
+
+        let strings = vec![
+            SsoString::from("inline"),
+            SsoString::from("a heap allocated string that is long enough"),
+            SsoString::from_static("a static string that is long enough to stay static"),
+        ];
+        let handle = std::thread::spawn(move || {
+            strings.into_iter().map(|mut s| { s.push_str("!"); s }).collect::<Vec<_>>()
+        });
+        let strings = handle.join().unwrap();
+        assert_eq!(strings[0], "inline!");
+        assert_eq!(strings[1], "a heap allocated string that is long enough!");
+        assert_eq!(strings[2], "a static string that is long enough to stay static!");
+
+        let shared = SsoString::from_static("shared between threads");
+        std::thread::scope(|scope| {
+            scope.spawn(|| assert_eq!(shared, "shared between threads"));
+            scope.spawn(|| assert_eq!(shared.len(), 22));
+        });
+    }
+
+    #[test]
+    fn test_from_str() {
+        let inline: SsoString = "abc".parse().unwrap();
+        assert_eq!(inline, "abc");
+
+        let heap = "a string that is too long to be stored inline".parse::<SsoString>().unwrap();
+        assert_eq!(heap, "a string that is too long to be stored inline");
+
+        fn parse_all<T: std::str::FromStr>(items: &[&str]) -> Vec<T> {
+            items.iter().filter_map(|s| s.parse().ok()).collect()
+        }
+        assert_eq!(parse_all::<SsoString>(&["x", "y"]), vec!["x", "y"]);
+    }
+
+    #[test]
+    fn test_from_char() {
+        for c in ['a', 'é', '€', '🦀'] {
+            let s = SsoString::from(c);
+            assert!(s.is_inlined());
+            assert_eq!(s.len(), c.len_utf8());
+            assert_eq!(s, c.to_string().as_str());
+        }
+
+        let chars = ['h', 'é', 'l', 'l', 'o'];
+        assert_eq!(SsoString::from(&chars[..]), "héllo");
+        assert_eq!(SsoString::from(&[][..]), "");
+
+        let long: Vec<char> = std::iter::repeat_n('x', 40).collect();
+        let s = SsoString::from(long.as_slice());
+        assert!(!s.is_inlined());
+        assert_eq!(s, "x".repeat(40).as_str());
+    }
+
+    #[test]
+    fn test_reverse_cmp() {
+        let inline = SsoString::from("hello");
+        let heap = SsoString::from("a heap allocated string that is long enough");
+
+        assert!("hello" == inline);
+        assert!(*"hello" == inline);
+        assert!(inline == *"hello");
+        let owned = String::from("hello");
+        assert!(owned == inline);
+        assert!("world" != inline);
+        assert!("a heap allocated string that is long enough" == heap);
+
+        assert!("abc" < inline);
+        assert!(*"zzz" > inline);
+        assert!(inline < *"zzz");
+        assert!(owned > heap);
+        assert_eq!("hello".partial_cmp(&inline), Some(std::cmp::Ordering::Equal));
+    }
+
+    #[test]
+    fn test_eq_chunked() {
+        // Lengths around the 16 byte chunk size and the inline capacity
+        for len in [0, 1, 15, 16, 17, 23, 24, 31, 32, 33, 100] {
+            let base: String = (0..len).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+            let a = SsoString::from(base.as_str());
+            assert_eq!(a, SsoString::from(base.as_str()));
+            assert_eq!(a, SsoString::from(base.clone()));
+
+            for i in 0..len {
+                let mut changed = base.clone().into_bytes();
+                changed[i] = b'Z';
+                let b = SsoString::from(String::from_utf8(changed).unwrap());
+                assert_ne!(a, b, "len {len}, diff at {i}");
+            }
+
+            let longer = SsoString::from(format!("{base}x"));
+            assert_ne!(a, longer);
+        }
+    }
+
+    #[test]
+    fn test_find_byte() {
+        let s = SsoString::from("key=value");
+        assert_eq!(s.find_byte(b'='), Some(3));
+        assert_eq!(s.rfind_byte(b'e'), Some(8));
+        assert_eq!(s.find_byte(b'#'), None);
+        assert_eq!(SsoString::new().find_byte(b'a'), None);
+        assert_eq!(SsoString::new().rfind_byte(b'a'), None);
+
+        // Every position and length around the word size, including non-ASCII bytes
+        for len in 0..40 {
+            let base: String = (0..len).map(|i| if i % 3 == 0 { 'é' } else { 'x' }).collect();
+            let s = SsoString::from(base.as_str());
+            for needle in [b'x', 0xA9, 0xC3, b'y'] {
+                assert_eq!(s.find_byte(needle), base.bytes().position(|b| b == needle));
+                assert_eq!(s.rfind_byte(needle), base.bytes().rposition(|b| b == needle));
+            }
+
+            for pos in 0..base.len() {
+                let mut bytes = vec![b'a'; base.len()];
+                bytes[pos] = b',';
+                let s = SsoString::from(String::from_utf8(bytes).unwrap());
+                assert_eq!(s.find_byte(b','), Some(pos));
+                assert_eq!(s.rfind_byte(b','), Some(pos));
+            }
+        }
+    }
+
+    #[test]
+    fn test_leak() {
+        let inputs = [
+            SsoString::from("inline"),
+            SsoString::from("a heap allocated string that is long enough"),
+            SsoString::from_static("a static string that is long enough to stay static"),
+            SsoString::new(),
+        ];
+        for s in inputs {
+            let expected = s.as_str().to_uppercase();
+            let leaked: &'static mut str = s.leak();
+            leaked.make_ascii_uppercase();
+            assert_eq!(*leaked, expected);
+
+            // Give the buffer back so the test doesn't actually leak; every input above is
+            // exactly sized after leaking
+            if !leaked.is_empty() {
+                let len = leaked.len();
+                drop(unsafe { String::from_raw_parts(leaked.as_mut_ptr(), len, len) });
+            }
+        }
+    }
+
+    #[test]
+    fn test_raw_parts_roundtrip() {
+        let inputs = [
+            SsoString::from("inline"),
+            SsoString::from("a heap allocated string that is long enough"),
+            SsoString::from_static("a static string that is long enough to stay static"),
+            SsoString::new(),
+        ];
+        for s in inputs {
+            let expected = s.as_str().to_owned();
+            let (ptr, len, cap) = s.into_raw_parts();
+            assert_eq!(len, expected.len());
+            assert!(cap >= len);
+
+            let mut rebuilt = unsafe { SsoString::from_raw_parts(ptr, len, cap) };
+            assert_eq!(rebuilt, expected.as_str());
+            assert!(!rebuilt.is_inlined() && !rebuilt.is_static());
+            rebuilt.push_str(" and more");
+            assert_eq!(rebuilt, format!("{expected} and more").as_str());
+        }
+
+        // Parts from a String with spare capacity
+        let mut string = String::with_capacity(64);
+        string.push_str("from a String");
+        let mut string = std::mem::ManuallyDrop::new(string);
+        let s = unsafe { SsoString::from_raw_parts(string.as_mut_vec().as_mut_ptr(), string.len(), string.capacity()) };
+        assert_eq!(s, "from a String");
+        assert_eq!(s.capacity(), 64);
+    }
+
+    #[test]
+    fn test_into_boxed_str() {
+        let boxed: Box<str> = SsoString::from("inline").into_boxed_str();
+        assert_eq!(&*boxed, "inline");
+
+        let boxed = SsoString::from_static("a static string that is long enough to stay static").into_boxed_str();
+        assert_eq!(&*boxed, "a static string that is long enough to stay static");
+
+        let mut heap = SsoString::with_capacity(128);
+        heap.push_str("a heap allocated string that is long enough");
+        let boxed = heap.into_boxed_str();
+        assert_eq!(&*boxed, "a heap allocated string that is long enough");
+
+        assert_eq!(&*SsoString::new().into_boxed_str(), "");
+    }
+
+    #[test]
+    fn test_clone_from_reuses_buffer() {
+        let mut dest = SsoString::with_capacity(100);
+        dest.push_str("previous contents that live on the heap");
+        let ptr = dest.as_ptr();
+
+        let source = SsoString::from("a heap allocated string that is long enough");
+        dest.clone_from(&source);
+        assert_eq!(dest, source);
+        assert_eq!(dest.as_ptr(), ptr);
+        assert_eq!(dest.capacity(), 100);
+
+        // Too big for the buffer
+        let big = SsoString::from("x".repeat(200));
+        dest.clone_from(&big);
+        assert_eq!(dest, big);
+        assert!(dest.capacity() >= 200);
+
+        // Representation mismatches take the generic path
+        let inline = SsoString::from("inline");
+        dest.clone_from(&inline);
+        assert_eq!(dest, "inline");
+        assert!(dest.is_inlined());
+
+        let stat = SsoString::from_static("a static string that is long enough to stay static");
+        dest.clone_from(&stat);
+        assert_eq!(dest, stat);
+        assert!(dest.is_static());
+
+        dest.clone_from(&source);
+        assert_eq!(dest, source);
+        assert!(!dest.is_inlined() && !dest.is_static());
+    }
+
+    #[test]
+    fn test_growth_factor() {
+        let mut s = SsoString::from("x".repeat(100));
+        assert_eq!(s.capacity(), 100);
+        s.push('y');
+        #[cfg(feature = "growth_2x")]
+        assert_eq!(s.capacity(), 200);
+        #[cfg(not(feature = "growth_2x"))]
+        assert_eq!(s.capacity(), 150);
+
+        // A big append grows straight to what's needed
+        s.push_str(&"z".repeat(1000));
+        assert_eq!(s.capacity(), 1101);
+
+        // Byte-at-a-time pushes reallocate a logarithmic number of times
+        let mut s = SsoString::new();
+        let mut reallocations = 0;
+        let mut cap = s.capacity();
+        for _ in 0..10_000 {
+            s.push('a');
+            if s.capacity() != cap {
+                reallocations += 1;
+                cap = s.capacity();
+            }
+        }
+        assert!(reallocations < 30, "{reallocations} reallocations");
+    }
+
+    #[test]
+    fn test_extend_from_within() {
+        let mut s = SsoString::from("abc");
+        s.extend_from_within(..);
+        assert_eq!(s, "abcabc");
+        assert!(s.is_inlined());
+
+        s.extend_from_within(1..=2);
+        assert_eq!(s, "abcabcbc");
+
+        // Inline to heap
+        let mut s = SsoString::from("héllo wörld");
+        s.extend_from_within(7..);
+        s.extend_from_within(..);
+        assert_eq!(s, "héllo wörldwörldhéllo wörldwörld");
+        assert!(!s.is_inlined());
+
+        // Static strings are copied before being written
+        let literal = "a static string that is long enough to stay static";
+        let mut s = SsoString::from_static(literal);
+        s.extend_from_within(..2);
+        assert_eq!(s, format!("{literal}a ").as_str());
+        assert!(!s.is_static());
+
+        let mut s = SsoString::from("abc");
+        s.extend_from_within(1..1);
+        assert_eq!(s, "abc");
+    }
+
+    #[test]
+    #[should_panic(expected = "char boundary")]
+    fn test_extend_from_within_not_char_boundary() {
+        let mut s = SsoString::from("héllo");
+        s.extend_from_within(..2);
+    }
+
+    #[test]
+    fn test_char_boundaries() {
+        let s = SsoString::from("aé€🦀");
+        let boundaries = [0, 1, 3, 6, 10];
+        for i in 0..=12 {
+            assert_eq!(s.is_char_boundary(i), s.as_str().is_char_boundary(i), "index {i}");
+            let floor = boundaries.iter().copied().filter(|&b| b <= i).max().unwrap();
+            let ceil = boundaries.iter().copied().find(|&b| b >= i).unwrap_or(10);
+            assert_eq!(s.floor_char_boundary(i), floor, "index {i}");
+            assert_eq!(s.ceil_char_boundary(i), ceil, "index {i}");
+        }
+
+        let empty = SsoString::new();
+        assert!(empty.is_char_boundary(0));
+        assert!(!empty.is_char_boundary(1));
+        assert_eq!(empty.floor_char_boundary(5), 0);
+        assert_eq!(empty.ceil_char_boundary(0), 0);
+
+        let heap = SsoString::from("a heap allocated string with ünïcödé in it");
+        let from: Vec<_> = heap.char_indices_from(25).collect();
+        let expected: Vec<_> = heap.char_indices().filter(|&(i, _)| i >= 25).collect();
+        assert_eq!(from, expected);
+        assert_eq!(heap.char_indices_from(heap.len()).next(), None);
+        assert_eq!(heap.char_indices_from(25).next_back(), Some((heap.len() - 1, 't')));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_char_indices_from_not_char_boundary() {
+        let s = SsoString::from("héllo");
+        let _ = s.char_indices_from(2);
+    }
+
+    #[test]
+    fn test_spare_capacity_mut() {
+        fn fill(s: &mut SsoString, src: &[u8]) {
+            let spare = s.spare_capacity_mut();
+            assert!(spare.len() >= src.len());
+            for (dst, &b) in spare.iter_mut().zip(src) {
+                dst.write(b);
+            }
+            let new_len = s.len() + src.len();
+            unsafe { s.set_len(new_len) };
+        }
+
+        let mut inline = SsoString::from("abc");
+        assert_eq!(inline.capacity_remaining(), inline.capacity() - 3);
+        assert_eq!(inline.spare_capacity_mut().len(), inline.capacity() - 3);
+        fill(&mut inline, b"def");
+        assert_eq!(inline, "abcdef");
+        assert!(inline.is_inlined());
+
+        let mut heap = SsoString::with_capacity(64);
+        heap.push_str("a heap allocated string ");
+        fill(&mut heap, b"filled in place");
+        assert_eq!(heap, "a heap allocated string filled in place");
+        assert_eq!(heap.capacity_remaining(), 64 - heap.len());
+
+        let literal = "a static string that is long enough to stay static";
+        let mut stat = SsoString::from_static(literal);
+        assert_eq!(stat.capacity_remaining(), 0);
+        assert!(stat.spare_capacity_mut().is_empty());
+        assert!(!stat.is_static());
+        assert_eq!(stat, literal);
+
+        let mut truncated = SsoString::from_static(literal);
+        truncated.truncate(30);
+        assert!(truncated.is_static());
+        assert_eq!(truncated.capacity_remaining(), 0);
+        assert!(truncated.spare_capacity_mut().is_empty());
+        assert_eq!(truncated, &literal[..30]);
+
+        let mut stat = SsoString::from_static("short static");
+        fill(&mut stat, b"!");
+        assert_eq!(stat, "short static!");
+
+        unsafe { heap.set_len(6) };
+        assert_eq!(heap, "a heap");
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        let mut s = SsoString::from("héllo wörld");
+        s.truncate_chars(20);
+        assert_eq!(s, "héllo wörld");
+        s.truncate_chars(11);
+        assert_eq!(s, "héllo wörld");
+        s.truncate_chars(8);
+        assert_eq!(s, "héllo wö");
+        s.truncate_chars(2);
+        assert_eq!(s, "hé");
+        s.truncate_chars(0);
+        assert_eq!(s, "");
+
+        let mut heap = SsoString::from("🦀".repeat(20));
+        heap.truncate_chars(7);
+        assert_eq!(heap, "🦀".repeat(7).as_str());
+        assert_eq!(heap.len(), 28);
+
+        let literal = "ünïcödé static string that is long enough to stay static";
+        let mut stat = SsoString::from_static(literal);
+        stat.truncate_chars(7);
+        assert_eq!(stat, "ünïcödé");
+        stat.push('!');
+        assert_eq!(stat, "ünïcödé!");
+    }
+
+    /// Small xorshift generator so the property tests are reproducible without extra dependencies.
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn string(&mut self, len: usize) -> String {
+            const CHARS: &[char] = &['a', 'z', '0', ' ', '-', 'é', 'ß', '€', '中', '🦀'];
+            (0..len).map(|_| CHARS[(self.next() % CHARS.len() as u64) as usize]).collect()
+        }
+    }
+
+    /// Every representation of the same content: the default one, static, and a forced heap buffer.
+    #[cfg(feature = "std")]
+    fn representations(text: &str) -> Vec<SsoString> {
+        let mut owned = std::mem::ManuallyDrop::new(text.to_owned());
+        let heap = unsafe { SsoString::from_raw_parts(owned.as_mut_vec().as_mut_ptr(), owned.len(), owned.capacity()) };
+        // The static ones don't outlive `text`
+        let stat = unsafe { SsoString::from_static_unchecked(text) };
+        vec![SsoString::from(text), stat, heap]
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_hash_eq_borrow_consistency() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let state = RandomState::new();
+        let mut rng = XorShift(0x2545_F491_4F6C_DD1D);
+        let mut sso_map = HashMap::new();
+        let mut string_map = HashMap::new();
+        let lengths = (0..40).chain((40..1000).step_by(if cfg!(miri) { 240 } else { 37 }));
+
+        for (i, len) in lengths.enumerate() {
+            let text = rng.string(len);
+            let reprs = representations(&text);
+
+            for (a, b) in reprs.iter().zip(reprs.iter().cycle().skip(1)) {
+                assert_eq!(a, b);
+                assert_eq!(state.hash_one(a), state.hash_one(b));
+            }
+            for s in &reprs {
+                let borrowed: &str = std::borrow::Borrow::borrow(s);
+                assert_eq!(borrowed, text);
+                assert_eq!(state.hash_one(s), state.hash_one(&text));
+                assert_eq!(state.hash_one(s), state.hash_one(text.as_str()));
+            }
+
+            // Static keys borrow `text`, which moves into `string_map` below, so keep them out of the map
+            for s in reprs.into_iter().filter(|s| !s.is_static()) {
+                sso_map.insert(s, i);
+            }
+            string_map.insert(text, i);
+        }
+
+        assert_eq!(sso_map.len(), string_map.len());
+        for (text, i) in &string_map {
+            assert_eq!(sso_map.get(text.as_str()), Some(i));
+            for s in representations(text) {
+                assert_eq!(sso_map.get(&s), Some(i));
+                assert_eq!(string_map.get(s.as_str()), Some(i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_box_str() {
+        let short: Box<str> = "short".into();
+        let s = SsoString::from(short);
+        assert_eq!(s, "short");
+        assert!(s.is_inlined());
+
+        let long: Box<str> = "a boxed string that is too long to be inlined".into();
+        let ptr = long.as_ptr();
+        let s = SsoString::from(long);
+        assert_eq!(s, "a boxed string that is too long to be inlined");
+        assert_eq!(s.as_ptr(), ptr);
+        assert_eq!(s.capacity(), s.len());
+    }
+
+    #[test]
+    fn test_matches_count_and_ranges() {
+        let s = SsoString::from("abcabcab");
+        assert_eq!(s.matches_count("ab"), 3);
+        assert_eq!(s.match_ranges("ab"), vec![0..2, 3..5, 6..8]);
+        assert_eq!(s.matches_count("x"), 0);
+        assert!(s.match_ranges("x").is_empty());
+
+        // Non-overlapping
+        let s = SsoString::from("aaaa");
+        assert_eq!(s.matches_count("aa"), 2);
+        assert_eq!(s.match_ranges("aa"), vec![0..2, 2..4]);
+
+        let heap = SsoString::from("héllo wörld, héllo again, and héllo once more");
+        let ranges = heap.match_ranges("héllo");
+        assert_eq!(heap.matches_count("héllo"), 3);
+        assert_eq!(ranges.len(), 3);
+        for range in ranges {
+            assert_eq!(&heap[range], "héllo");
+        }
+    }
+
+    #[test]
+    fn test_try_push_str() {
+        let mut s = SsoString::from("abc");
+        assert_eq!(s.try_push_str("def"), Ok(()));
+        assert_eq!(s, "abcdef");
+        assert!(s.is_inlined());
+
+        assert_eq!(s.try_push_str(&"x".repeat(50)), Ok(()));
+        assert_eq!(s.len(), 56);
+        assert!(!s.is_inlined());
+
+        let mut stat = SsoString::from_static("a static string that is long enough to stay static");
+        assert_eq!(stat.try_push_str("!"), Ok(()));
+        assert_eq!(stat, "a static string that is long enough to stay static!");
+        assert!(!stat.is_static());
+    }
+
+    #[test]
+    fn test_split_owned() {
+        let s = SsoString::from("  the quick\tbrown\n fox  ");
+        let tokens = s.split_whitespace_owned();
+        assert_eq!(tokens, vec!["the", "quick", "brown", "fox"]);
+        assert!(tokens.iter().all(SsoString::is_inlined));
+        assert!(SsoString::from(" \t ").split_whitespace_owned().is_empty());
+
+        let csv = SsoString::from("a,,b,a field that is too long to be stored inline");
+        let fields = csv.split_owned(",");
+        assert_eq!(fields, vec!["a", "", "b", "a field that is too long to be stored inline"]);
+        assert!(!fields[3].is_inlined());
+        assert_eq!(SsoString::new().split_owned(","), vec![""]);
+    }
+
+    #[test]
+    fn test_trimmed() {
+        let s = SsoString::from("  \t padded \n");
+        assert_eq!(s.trimmed(), "padded");
+        assert_eq!(s.trimmed_start(), "padded \n");
+        assert_eq!(s.trimmed_end(), "  \t padded");
+        assert!(s.trimmed().is_inlined());
+
+        let heap = SsoString::from(format!("   {}   ", "x".repeat(30)));
+        let trimmed = heap.trimmed();
+        drop(heap);
+        assert_eq!(trimmed, "x".repeat(30).as_str());
+
+        let literal = "   a static string that is long enough to stay static   ";
+        let stat = SsoString::from_static(literal);
+        let trimmed = stat.trimmed();
+        assert!(trimmed.is_static());
+        assert_eq!(trimmed, literal.trim());
+        assert_eq!(trimmed.as_ptr(), literal.trim().as_ptr());
+
+        assert_eq!(SsoString::from("   ").trimmed(), "");
+    }
+
+    #[test]
+    fn test_append() {
+        let mut s = SsoString::from("abc");
+        s.append(SsoString::from("def"));
+        assert_eq!(s, "abcdef");
+        assert!(s.is_inlined());
+
+        // Inline self, heap other with spare room: other's buffer is reused
+        let mut other = SsoString::with_capacity(64);
+        other.push_str("a heap allocated string that is long enough");
+        let ptr = other.as_ptr();
+        let mut s = SsoString::from("prefix: ");
+        s.append(other);
+        assert_eq!(s, "prefix: a heap allocated string that is long enough");
+        assert_eq!(s.as_ptr(), ptr);
+
+        let mut empty = SsoString::new();
+        let other = SsoString::from("another heap allocated string that is long");
+        let ptr = other.as_ptr();
+        empty.append(other);
+        assert_eq!(empty, "another heap allocated string that is long");
+        assert_eq!(empty.as_ptr(), ptr);
+
+        // No room in other, and static other: plain copy
+        let mut s = SsoString::from("prefix: ");
+        s.append(SsoString::from("exactly sized heap allocated string"));
+        assert_eq!(s, "prefix: exactly sized heap allocated string");
+        let literal = "a static string that is long enough to stay static";
+        let mut s = SsoString::from("x");
+        s.append(SsoString::from_static(literal));
+        assert_eq!(s, format!("x{literal}").as_str());
+        assert!(!s.is_static());
+
+        let mut heap = SsoString::from("a heap allocated string that is long enough");
+        heap.append(SsoString::from("!"));
+        assert_eq!(heap, "a heap allocated string that is long enough!");
+    }
+
+    #[test]
+    fn test_format_sso() {
+        let name = "world";
+        let s = format_sso!("hello {name}, {}!", 42);
+        assert_eq!(s, "hello world, 42!");
+        assert!(s.is_inlined());
+
+        let long = format_sso!("{:>40}", "right aligned");
+        assert_eq!(long, format!("{:>40}", "right aligned").as_str());
+        assert!(!long.is_inlined());
+
+        assert_eq!(format_sso!(""), "");
+        assert_eq!(format_sso!("{:?}", SsoString::from("quoted")), "\"quoted\"");
+    }
+
+    #[test]
+    fn test_retain_ascii() {
+        let mut s = SsoString::from("a1b2c3");
+        s.retain_ascii(|b| b.is_ascii_alphabetic());
+        assert_eq!(s, "abc");
+
+        let mut heap = SsoString::from("phone: +1 (555) 010-9999, ext. 42");
+        heap.retain_ascii(|b| b.is_ascii_digit());
+        assert_eq!(heap, "1555010999942");
+
+        // Non-ASCII bytes are never passed to the predicate and always kept
+        let mut s = SsoString::from("héllo wörld");
+        s.retain_ascii(|b| {
+            assert!(b.is_ascii());
+            b != b'l' && b != b' '
+        });
+        assert_eq!(s, "héowörd");
+
+        let literal = "a static string that is long enough to stay static";
+        let mut stat = SsoString::from_static(literal);
+        stat.retain_ascii(|b| b != b' ');
+        assert_eq!(stat, literal.replace(' ', "").as_str());
+        assert!(!stat.is_static());
+    }
+
+    #[test]
+    fn test_retain_ascii_panicking_predicate_keeps_valid_utf8() {
+        for text in ["aéb", "a heap string with é and some more bytes after it"] {
+            let mut s = SsoString::from(text);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                s.retain_ascii(|b| match b {
+                    b'a' => false,
+                    b'b' => panic!("predicate panicked"),
+                    _ => true,
+                });
+            }));
+            assert!(result.is_err());
+            assert!(std::str::from_utf8(s.as_bytes()).is_ok());
+            // The 'a's before the panic are gone, everything from the panic on is untouched
+            let at = text.find('b').unwrap();
+            assert_eq!(s, text[..at].replace('a', "") + &text[at..]);
+        }
+    }
+
+    #[test]
+    fn test_heap_capacity_and_allocation_size() {
+        let inline = SsoString::from("inline");
+        assert_eq!(inline.heap_capacity(), None);
+        assert_eq!(inline.allocation_size(), 0);
+
+        let stat = SsoString::from_static("a static string that is long enough to stay static");
+        assert_eq!(stat.heap_capacity(), None);
+        assert_eq!(stat.allocation_size(), 0);
+
+        let mut heap = SsoString::with_capacity(100);
+        heap.push_str("a heap allocated string that is long enough");
+        assert_eq!(heap.heap_capacity(), Some(100));
+        assert_eq!(heap.allocation_size(), 100);
+
+        let exact = SsoString::from("x".repeat(30));
+        assert_eq!(exact.heap_capacity(), Some(exact.capacity()));
+    }
+
+    #[test]
+    fn test_representation() {
+        let mut s = SsoString::from("inline");
+        assert_eq!(s.representation(), Representation::Inline);
+        s.push_str(" until it grows past the inline capacity");
+        assert_eq!(s.representation(), Representation::Heap);
+
+        let mut stat = SsoString::from_static("static");
+        assert_eq!(stat.representation(), Representation::Static);
+        stat.push('!');
+        assert_eq!(stat.representation(), Representation::Inline);
+
+        assert_eq!(SsoString::new().representation(), Representation::Inline);
+        assert_eq!(SsoString::with_capacity(100).representation(), Representation::Heap);
+    }
+
+    #[test]
+    fn test_from_char_iter_exact() {
+        let chars = "a heap allocated string with ünïcödé".chars();
+        let byte_len = chars.clone().map(char::len_utf8).sum();
+        let s = SsoString::from_char_iter_exact(chars, byte_len);
+        assert_eq!(s, "a heap allocated string with ünïcödé");
+        assert_eq!(s.capacity(), byte_len);
+
+        let s = SsoString::from_char_iter_exact("short".chars(), 5);
+        assert_eq!(s, "short");
+        assert!(s.is_inlined());
+
+        // Wrong hints still produce the right string
+        let s = SsoString::from_char_iter_exact(std::iter::repeat_n('x', 100), 10);
+        assert_eq!(s, "x".repeat(100).as_str());
+        let s = SsoString::from_char_iter_exact("abc".chars(), 100);
+        assert_eq!(s, "abc");
+        assert_eq!(s.capacity(), 100);
+    }
+
+    #[test]
+    fn test_as_ref_bytes_os_str_path() {
+        fn byte_len(b: impl AsRef<[u8]>) -> usize {
+            b.as_ref().len()
+        }
+
+        let s = SsoString::from("héllo");
+        assert_eq!(byte_len(&s), 6);
+        let bytes: &[u8] = s.as_ref();
+        assert_eq!(bytes, "héllo".as_bytes());
+
+        #[cfg(feature = "std")]
+        {
+            use std::ffi::OsStr;
+            use std::path::Path;
+
+            let path = SsoString::from("some/dir/file.txt");
+            let os: &OsStr = path.as_ref();
+            assert_eq!(os, OsStr::new("some/dir/file.txt"));
+            let p: &Path = path.as_ref();
+            assert_eq!(p.file_name(), Some(OsStr::new("file.txt")));
+            fn file_stem(p: impl AsRef<Path>) -> Option<String> {
+                p.as_ref().file_stem().map(|s| s.to_string_lossy().into_owned())
+            }
+            assert_eq!(file_stem(&path).as_deref(), Some("file"));
+            assert!(Path::new("some").join(&path).ends_with("file.txt"));
+        }
+    }
+
+    #[test]
+    fn test_try_from_bytes() {
+        let s = SsoString::try_from("héllo".as_bytes()).unwrap();
+        assert_eq!(s, "héllo");
+        assert!(s.is_inlined());
+        assert!(SsoString::try_from(&b"bad \xff byte"[..]).is_err());
+
+        let long = b"a heap allocated string that is long enough".to_vec();
+        let ptr = long.as_ptr();
+        let s = SsoString::try_from(long).unwrap();
+        assert_eq!(s, "a heap allocated string that is long enough");
+        assert_eq!(s.as_ptr(), ptr);
+
+        let err = SsoString::try_from(vec![b'a', 0xC3]).unwrap_err();
+        assert_eq!(err.valid_up_to(), 1);
+    }
+
+    #[test]
+    fn test_first_last_nth_char() {
+        let s = SsoString::from("héllo wörld 🦀");
+        assert_eq!(s.first_char(), Some('h'));
+        assert_eq!(s.last_char(), Some('🦀'));
+        assert_eq!(s.nth_char(1), Some('é'));
+        assert_eq!(s.nth_char(7), Some('ö'));
+        assert_eq!(s.nth_char(12), Some('🦀'));
+        assert_eq!(s.nth_char(13), None);
+        assert_eq!(s.chars_rev().collect::<String>(), "🦀 dlröw olléh");
+
+        let empty = SsoString::new();
+        assert_eq!(empty.first_char(), None);
+        assert_eq!(empty.last_char(), None);
+        assert_eq!(empty.nth_char(0), None);
+    }
+
+    #[test]
+    fn test_from_utf8_inlines_short_input() {
+        let mut bytes = Vec::with_capacity(100);
+        bytes.extend_from_slice("héllo".as_bytes());
+        let s = SsoString::from_utf8(bytes).unwrap();
+        assert!(s.is_inlined());
+        assert_eq!(s, "héllo");
+
+        let max = vec![b'x'; 23];
+        let s = unsafe { SsoString::from_utf8_unchecked(max) };
+        assert_eq!(s.is_inlined(), std::mem::size_of::<usize>() == 8);
+
+        let mut long = Vec::with_capacity(100);
+        long.extend_from_slice(b"a vec long enough to be adopted as a heap buffer");
+        let ptr = long.as_ptr();
+        let s = SsoString::from_utf8(long).unwrap();
+        assert!(!s.is_inlined());
+        assert_eq!(s.as_ptr(), ptr);
+        assert_eq!(s.capacity(), 100);
+    }
+
+    #[test]
+    fn test_insert_remove_at_char() {
+        let mut s = SsoString::from("hllo wrld");
+        s.insert_at_char(1, 'é');
+        s.insert_at_char(7, 'ö');
+        assert_eq!(s, "héllo wörld");
+        s.insert_at_char(11, '🦀');
+        s.insert_at_char(0, '¡');
+        assert_eq!(s, "¡héllo wörld🦀");
+
+        assert_eq!(s.remove_at_char(0), '¡');
+        assert_eq!(s.remove_at_char(11), '🦀');
+        assert_eq!(s.remove_at_char(1), 'é');
+        assert_eq!(s, "hllo wörld");
+
+        let literal = "a static string that is long enough to stay static";
+        let mut stat = SsoString::from_static(literal);
+        assert_eq!(stat.remove_at_char(0), 'a');
+        stat.insert_at_char(0, 'Ä');
+        assert_eq!(stat, format!("Ä{}", &literal[1..]).as_str());
+        assert!(!stat.is_static());
+
+        let mut empty = SsoString::new();
+        empty.insert_at_char(0, 'x');
+        assert_eq!(empty, "x");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_insert_at_char_out_of_bounds() {
+        let mut s = SsoString::from("héllo");
+        s.insert_at_char(6, 'x');
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_remove_at_char_out_of_bounds() {
+        let mut s = SsoString::from("héllo");
+        s.remove_at_char(5);
+    }
+
+    #[test]
+    fn test_shrink_or_inline() {
+        let mut s = SsoString::from("a heap allocated string that is long enough");
+        s.shrink_or_inline();
+        assert!(!s.is_inlined());
+
+        s.truncate(6);
+        s.shrink_or_inline();
+        assert!(s.is_inlined());
+        assert_eq!(s, "a heap");
+        s.push_str(" again");
+        assert_eq!(s, "a heap again");
+
+        let mut empty_heap = SsoString::with_capacity(100);
+        empty_heap.shrink_or_inline();
+        assert!(empty_heap.is_inlined());
+        assert_eq!(empty_heap, "");
+
+        let mut stat = SsoString::from_static("static");
+        stat.shrink_or_inline();
+        assert!(stat.is_static());
+    }
+
+    #[test]
+    fn test_max_capacity() {
+        assert_eq!(SsoString::MAX_CAPACITY, usize::MAX >> 8);
+        let mut s = SsoString::from("abc");
+        assert_eq!(s.try_reserve(SsoString::MAX_CAPACITY), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(s.try_reserve(SsoString::MAX_CAPACITY - 3 + 1), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(s, "abc");
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_with_capacity_over_max() {
+        let _ = SsoString::with_capacity(SsoString::MAX_CAPACITY + 1);
+    }
+
+    #[test]
+    fn test_heap_str_inline_str() {
+        let inline = SsoString::from("inline");
+        let heap = SsoString::from("a heap allocated string that is long enough");
+        let stat = SsoString::from_static("static");
+        unsafe {
+            assert_eq!(inline.inline_str(), "inline");
+            assert_eq!(heap.heap_str(), "a heap allocated string that is long enough");
+            assert_eq!(stat.heap_str(), "static");
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "heap_str called on an inline string")]
+    fn test_heap_str_on_inline_debug_panics() {
+        let inline = SsoString::from("inline");
+        let _ = unsafe { inline.heap_str() };
+    }
+
+    #[test]
+    fn test_push_bytes_unchecked() {
+        let mut s = SsoString::new();
+        for chunk in ["héllo", " ", "wörld", ", now long enough for the heap"] {
+            unsafe { s.push_bytes_unchecked(chunk.as_bytes()) };
+        }
+        assert_eq!(s, "héllo wörld, now long enough for the heap");
+
+        let mut stat = SsoString::from_static("static");
+        unsafe { stat.push_bytes_unchecked(b"!") };
+        assert_eq!(stat, "static!");
+    }
+
+    #[test]
+    fn test_as_static() {
+        let literal: &'static str = "a static string that is long enough to stay static";
+        let s = SsoString::from_static(literal);
+        assert_eq!(s.as_static(), Some(literal));
+        assert_eq!(s.as_static().unwrap().as_ptr(), literal.as_ptr());
+
+        // Clones, From<&SsoString> and trimmed views share the static data
+        let clone = SsoString::from(&s);
+        assert_eq!(clone.as_static(), Some(literal));
+        let padded = SsoString::from_static("  static  ");
+        assert_eq!(padded.trimmed().as_static(), Some("static"));
+
+        let mut written = s.clone();
+        written.push('!');
+        assert_eq!(written.as_static(), None);
+
+        assert_eq!(SsoString::from("inline").as_static(), None);
+        assert_eq!(SsoString::from(literal).as_static(), None);
+
+        let owned = String::from("not actually static");
+        let borrowed = unsafe { SsoString::from_static_unchecked(&owned) };
+        assert!(borrowed.is_static());
+        assert_eq!(borrowed.as_static(), None);
+        assert_eq!(borrowed.trimmed().as_static(), None);
+    }
+
+    #[test]
+    fn test_pad_to() {
+        let mut s = SsoString::from("42");
+        s.pad_to(5, '0');
+        assert_eq!(s, "42000");
+        s.pad_to(3, '0');
+        assert_eq!(s, "42000");
+        assert!(s.is_inlined());
+
+        // Inline to heap
+        s.pad_to(40, '.');
+        assert_eq!(s.len(), 40);
+        assert_eq!(s, format!("42000{}", ".".repeat(35)).as_str());
+
+        // Multi-byte fill: bytes may overshoot, chars are exact
+        let mut s = SsoString::from("ab");
+        s.pad_to(7, '€');
+        assert_eq!(s, "ab€€");
+        let mut s = SsoString::from("héllo");
+        s.pad_to_chars(8, '·');
+        assert_eq!(s, "héllo···");
+        assert_eq!(s.chars().count(), 8);
+
+        let mut stat = SsoString::from_static("a static string that is long enough to stay static");
+        stat.pad_to(55, '-');
+        assert_eq!(stat, "a static string that is long enough to stay static-----");
+        assert!(!stat.is_static());
+    }
+
+    #[test]
+    fn test_concat_join() {
+        let s = SsoString::concat(["ab", "cd", "ef"]);
+        assert_eq!(s, "abcdef");
+        assert!(s.is_inlined());
+
+        let pieces = vec![String::from("a heap "), String::from("allocated "), String::from("concatenation")];
+        let s = SsoString::concat(&pieces);
+        assert_eq!(s, "a heap allocated concatenation");
+        assert_eq!(s.capacity(), s.len());
+
+        let s = SsoString::join(["path", "to", "some", "deeply", "nested", "file"], "/");
+        assert_eq!(s, "path/to/some/deeply/nested/file");
+        assert_eq!(s.capacity(), s.len());
+
+        let words = [SsoString::from("x"), SsoString::from("y")];
+        assert_eq!(SsoString::join(words.iter().map(SsoString::as_str), ", "), "x, y");
+        assert_eq!(SsoString::join(Vec::<&str>::new(), ", "), "");
+        assert_eq!(SsoString::join(["only"], ", "), "only");
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        let s = SsoString::from("  hello \t\n  wörld  ");
+        let normalized = s.normalize_whitespace();
+        assert_eq!(normalized, "hello wörld");
+        assert!(normalized.is_inlined());
+
+        assert_eq!(SsoString::from("already normal").normalize_whitespace(), "already normal");
+        assert_eq!(SsoString::from(" \u{3000}\u{a0} ").normalize_whitespace(), "");
+        assert_eq!(SsoString::new().normalize_whitespace(), "");
+
+        let long = SsoString::from("a   heap\n\nallocated\tstring   with   lots   of   space");
+        assert_eq!(long.normalize_whitespace(), "a heap allocated string with lots of space");
+    }
+
+    #[test]
+    fn test_starts_ends_with_ignore_ascii_case() {
+        let s = SsoString::from("Content-Type");
+        assert!(s.starts_with_ignore_ascii_case("content-"));
+        assert!(s.starts_with_ignore_ascii_case("CONTENT-TYPE"));
+        assert!(s.ends_with_ignore_ascii_case("-TYPE"));
+        assert!(s.starts_with_ignore_ascii_case(""));
+        assert!(!s.starts_with_ignore_ascii_case("Content-Type-Extra"));
+        assert!(!s.ends_with_ignore_ascii_case("length"));
+
+        let url = SsoString::from("HTTPS://example.com/some/long/path/Index.HTML");
+        assert!(url.starts_with_ignore_ascii_case("https://"));
+        assert!(url.ends_with_ignore_ascii_case(".html"));
+
+        // Only ASCII letters are folded
+        let s = SsoString::from("Ärger");
+        assert!(!s.starts_with_ignore_ascii_case("ä"));
+        assert!(s.ends_with_ignore_ascii_case("RGER"));
+    }
+
+    #[test]
+    fn test_make_mut() {
+        static LITERAL: &str = "a static string that is long enough to stay static";
+        let mut s = SsoString::from_static(LITERAL);
+        let ptr = s.as_ptr();
+
+        s.make_mut().make_ascii_uppercase();
+        assert_eq!(s, LITERAL.to_ascii_uppercase().as_str());
+        assert!(!s.is_static());
+        assert_ne!(s.as_ptr(), ptr);
+        assert_eq!(LITERAL, "a static string that is long enough to stay static");
+
+        let mut short = SsoString::from_static("short");
+        short.make_mut().make_ascii_uppercase();
+        assert_eq!(short, "SHORT");
+        assert!(short.is_inlined());
+
+        let mut heap = SsoString::from("a heap allocated string that is long enough");
+        let ptr = heap.as_ptr();
+        heap.make_mut().make_ascii_uppercase();
+        assert_eq!(heap.as_ptr(), ptr);
+        assert_eq!(heap, "A HEAP ALLOCATED STRING THAT IS LONG ENOUGH");
+    }
+
+    #[test]
+    fn test_char_count() {
+        let s = SsoString::from("héllo 🦀");
+        assert_eq!(s.len(), 11);
+        assert_eq!(s.char_count(), 7);
+        assert_eq!(SsoString::new().char_count(), 0);
+        assert_eq!(SsoString::from("€".repeat(30)).char_count(), 30);
+    }
+
+    #[test]
+    fn test_custom_inline_capacity() {
+        type Sso40 = SsoStringN<40>;
+        assert_eq!(std::mem::size_of::<SsoString>(), std::mem::size_of::<String>());
+        assert_eq!(std::mem::size_of::<Sso40>(), 48);
+
+        let s = "a string of exactly forty bytes in size";
+        let mut s40 = Sso40::from(s);
+        assert_eq!(s40.len(), 40 - 1);
+        assert_eq!(s40.representation(), Representation::Inline);
+        s40.push('!');
+        assert_eq!(s40.representation(), Representation::Inline);
+        assert_eq!(s40.capacity(), 40);
+        assert_eq!(s40, format!("{s}!"));
+
+        s40.push('?');
+        assert_eq!(s40.representation(), Representation::Heap);
+        assert_eq!(s40, format!("{s}!?"));
+        s40.truncate(10);
+        s40.shrink_or_inline();
+        assert_eq!(s40.representation(), Representation::Inline);
+        assert_eq!(s40, &s[..10]);
+
+        // The default keeps its old threshold, and a smaller N can't shrink below it
+        assert_eq!(SsoString::from(s).representation(), Representation::Heap);
+        assert_eq!(SsoStringN::<8>::from("twenty-three bytes long").representation(), Representation::Inline);
+        assert_eq!(SsoStringN::<8>::new().capacity(), DEFAULT_INLINE_CAPACITY);
+
+        let long = Sso40::from_static("static data longer than the forty inline bytes");
+        let mut copy = long.clone();
+        copy.push_str(" and then some");
+        assert_eq!(long.representation(), Representation::Static);
+        assert!(copy.ends_with("bytes and then some"));
+        assert_eq!(copy.drain(..7).collect::<String>(), "static ");
+    }
+
+    #[test]
+    fn test_zero_capacity_buffers_are_never_freed() {
+        let mut pushed = zero_capacity_heap();
+        pushed.push_str("");
+        assert_eq!(pushed.capacity(), 0);
+        let mut target = zero_capacity_heap();
+        target.clone_from(&pushed);
+        target.extend_from_within(..);
+        assert_eq!(target.capacity(), 0);
+
+        assert_eq!(zero_capacity_heap().into_string().capacity(), 0);
+        assert_eq!(zero_capacity_heap().into_bytes(), b"");
+
+        let (ptr, len, cap) = zero_capacity_heap().into_raw_parts();
+        assert_eq!((len, cap), (0, 0));
+        let rebuilt = unsafe { SsoString::from_raw_parts(ptr, len, cap) };
+        assert_eq!(rebuilt, "");
+
+        let mut from_vec = String::new().into_bytes();
+        let empty = unsafe { SsoString::from_raw_parts(from_vec.as_mut_ptr(), 0, from_vec.capacity()) };
+        std::mem::forget(from_vec);
+        drop(empty);
+
+        // A real allocation is still freed once the string is emptied
+        let mut real = SsoString::with_capacity(64);
+        real.push_str("data");
+        real.truncate(0);
+        assert_eq!(real.capacity(), 64);
+    }
+
+    #[test]
+    fn test_const_new_is_zeroed_inline() {
+        const EMPTY: SsoString = SsoString::new();
+        static TABLE: [SsoString; 3] = [EMPTY; 3];
+
+        for s in TABLE.iter().chain([&EMPTY, &SsoString::default()]) {
+            assert_eq!(s.len(), 0);
+            assert!(s.is_inlined());
+            assert_eq!(s.as_str(), "");
+        }
+
+        // Only the inline flag is set, the rest of the struct, pointer field included, is zero
+        let bytes: [u8; std::mem::size_of::<SsoString>()] = unsafe { std::mem::transmute(SsoString::default()) };
+        assert_eq!(bytes[0], 0b1000_0000);
+        assert!(bytes[1..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_remove_matches_and_retain_ranges() {
+        let mut s = SsoString::from("a--b--c----d--");
+        s.remove_matches("--");
+        assert_eq!(s, "abcd");
+        s.remove_matches("");
+        assert_eq!(s, "abcd");
+
+        static LONG: &str = "ünïcödé, ünïcödé and more ünïcödé than fits inline";
+        let mut s = SsoString::from_static(LONG);
+        s.remove_matches("ünïcödé");
+        assert_eq!(s, ",  and more  than fits inline");
+        assert!(!s.is_static());
+
+        let mut s = SsoString::from("keep only the vowels here");
+        let ranges = s.match_ranges("e");
+        s.retain_ranges(ranges);
+        assert_eq!(s, "eeeeee");
+
+        let mut s = SsoString::from_static("héllo, wörld and then some more text");
+        s.retain_ranges([0..6, 8..14, 14..14]);
+        assert_eq!(s, "héllowörld");
+    }
+
+    #[test]
+    #[should_panic(expected = "ranges must be sorted")]
+    fn test_retain_ranges_rejects_overlap() {
+        SsoString::from("overlapping").retain_ranges([0..4, 2..6]);
+    }
+
+    #[test]
+    fn test_retain_ranges_panic_leaves_valid_utf8() {
+        let bad_ranges: [&[std::ops::Range<usize>]; 3] = [&[1..3, 0..1], &[0..1, 2..3], &[0..1, 1..9]];
+        for ranges in bad_ranges {
+            let mut s = SsoString::from("xé");
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                s.retain_ranges(ranges.iter().cloned());
+            }));
+            assert!(result.is_err());
+            assert!(std::str::from_utf8(s.as_bytes()).is_ok());
+            assert_eq!(s, "");
+        }
+
+        let mut s = SsoString::from("a heap string whose iterator panics half way");
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            s.retain_ranges((0..4).map(|i| if i < 2 { i * 2..i * 2 + 1 } else { panic!("iterator panicked") }));
+        }));
+        assert!(result.is_err());
+        assert!(std::str::from_utf8(s.as_bytes()).is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_to() {
+        let mut out = Vec::new();
+        assert_eq!(SsoString::from("inline, ").write_to(&mut out).unwrap(), 8);
+        let heap = SsoString::from("then a heap string longer than inline");
+        assert_eq!(heap.write_to(&mut out).unwrap(), heap.len());
+        let w: &mut dyn std::io::Write = &mut out;
+        SsoString::from_static("!").write_to(w).unwrap();
+        assert_eq!(out, b"inline, then a heap string longer than inline!");
+    }
+
+    #[test]
+    fn test_eq_str_cow_box() {
+        use std::borrow::Cow;
+
+        let inline = SsoString::from("short");
+        let heap = SsoString::from("a string long enough to live on the heap");
+        assert!(inline.eq_str("short"));
+        assert!(!inline.eq_str("shorts"));
+        assert!(heap.eq_str("a string long enough to live on the heap"));
+
+        let borrowed: Cow<str> = Cow::Borrowed("short");
+        let owned: Cow<str> = Cow::Owned("a string long enough to live on the heap".to_string());
+        assert_eq!(inline, borrowed);
+        assert_eq!(borrowed, inline);
+        assert_eq!(heap, owned);
+        assert_eq!(owned, heap);
+        assert!(inline != owned);
+
+        let boxed: Box<str> = "short".into();
+        assert_eq!(inline, boxed);
+        assert_eq!(boxed, inline);
+        assert!(heap != boxed);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn test_from_utf8_simd_matches_from_utf8() {
+        let valid = [
+            "".to_string(),
+            "short".to_string(),
+            "ascii ".repeat(20),
+            "ascii first, then ünïcödé spilling across chunk borders: 日本語 🦀🦀".repeat(3),
+            "ééééééééééééééééééééééééééééééééééé".to_string(),
+            "a".repeat(100) + "é" + &"b".repeat(1100) + "日本語" + &"c".repeat(200),
+        ];
+        for s in &valid {
+            let simd = SsoString::from_utf8_simd(s.clone().into_bytes()).unwrap();
+            assert_eq!(simd, s.as_str());
+            assert_eq!(simd.is_inlined(), SsoString::from_utf8(s.clone().into_bytes()).unwrap().is_inlined());
+        }
+
+        let mut invalid = Vec::new();
+        for (i, s) in valid.iter().enumerate() {
+            let mut bytes = s.clone().into_bytes();
+            bytes.insert(bytes.len() / 2, 0xFF);
+            invalid.push(bytes);
+            let mut truncated = s.clone().into_bytes();
+            truncated.extend_from_slice(&[b'x'; 17][..i * 3]);
+            truncated.extend_from_slice(&"🦀".as_bytes()[..2]);
+            invalid.push(truncated);
+        }
+        invalid.push(vec![b'a', 0x80, 0x80, 0x80, 0x80]);
+        for bytes in invalid {
+            let expected = std::str::from_utf8(&bytes).unwrap_err();
+            assert_eq!(SsoString::from_utf8_simd(bytes).unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn test_capacity_for_matches_growth() {
+        let inline = SsoString::new().capacity();
+        assert_eq!(SsoString::capacity_for(0), inline);
+        assert_eq!(SsoString::capacity_for(inline), inline);
+        assert_eq!(SsoStringN::<40>::capacity_for(40), 40);
+
+        for len in [inline + 1, 30, 34, 35, 100, 4096] {
+            let mut s = SsoString::new();
+            s.push_str(&"x".repeat(len));
+            assert_eq!(SsoString::capacity_for(len), s.capacity(), "len {len}");
+            assert!(SsoString::capacity_for(len) >= len);
+        }
+    }
+
+    #[test]
+    fn test_owned_split_variants() {
+        let s = SsoString::from("a,b,,a much longer piece that has to go on the heap,");
+        assert_eq!(s.split_terminator_owned(","), ["a", "b", "", "a much longer piece that has to go on the heap"]);
+        assert_eq!(s.rsplit_owned(","), ["", "a much longer piece that has to go on the heap", "", "b", "a"]);
+        assert_eq!(s.splitn_owned(2, ","), ["a", "b,,a much longer piece that has to go on the heap,"]);
+        assert_eq!(s.rsplitn_owned(3, ","), ["", "a much longer piece that has to go on the heap", "a,b,"]);
+        assert!(s.splitn_owned(0, ",").is_empty());
+
+        let pieces = s.rsplit_owned(",");
+        assert!(pieces[4].is_inlined());
+        assert!(!pieces[1].is_inlined());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_interner() {
+        use sso_string::intern::Interner;
+
+        let mut interner = Interner::new();
+        assert!(interner.is_empty());
+        let long = "an interned string long enough for a heap buffer";
+        let a = interner.intern("short");
+        let b = interner.intern(long);
+        assert_ne!(a, b);
+        assert_eq!(interner.intern("short"), a);
+        assert_eq!(interner.intern(long), b);
+        assert_eq!(interner.len(), 2);
+
+        // Force the table to reallocate; ids and heap-backed keys stay valid
+        let ids: Vec<_> = (0..1000).map(|i| interner.intern(&format!("string number {i} of many, long enough"))).collect();
+        assert_eq!(interner.resolve(a), "short");
+        assert_eq!(interner.resolve(b), long);
+        assert_eq!(interner.get(long), Some(b));
+        assert_eq!(interner.get("missing"), None);
+        assert_eq!(interner.resolve(ids[500]), "string number 500 of many, long enough");
+        assert_eq!(interner.intern("string number 999 of many, long enough"), ids[999]);
+        assert_eq!(interner.len(), 1002);
+    }
+
+    #[test]
+    fn test_from_static_bytes() {
+        static TABLE: &[u8] = "a compile-time byte table, known to be valid UTF-8: ✓".as_bytes();
+        let s = SsoString::from_static_bytes(TABLE).unwrap();
+        assert!(s.is_static());
+        assert_eq!(s.as_ptr(), TABLE.as_ptr());
+        assert_eq!(s.as_static().map(str::as_bytes), Some(TABLE));
+
+        let unchecked = unsafe { SsoString::from_static_bytes_unchecked(TABLE) };
+        assert_eq!(unchecked, s);
+        assert!(unchecked.as_static().is_some());
+
+        assert!(SsoString::from_static_bytes(b"invalid \xFF byte").is_err());
+    }
+
+    #[test]
+    fn test_count_and_contains_char() {
+        let csv = SsoString::from("id,name,city\n1,Zoë,Zürich\n2,Łukasz,Kraków\n");
+        assert_eq!(csv.count_char(','), 6);
+        assert_eq!(csv.count_char('\n'), 3);
+        assert_eq!(csv.count_char('ü'), 1);
+        assert_eq!(csv.count_char('ó'), 1);
+        assert_eq!(csv.count_char(';'), 0);
+        assert!(csv.contains_char('Ł'));
+        assert!(csv.contains_char('Z'));
+        assert!(!csv.contains_char('$'));
+        assert!(!csv.contains_char('ß'));
+
+        let short = SsoString::from("a.b.c");
+        assert!(short.is_inlined());
+        assert_eq!(short.count_char('.'), 2);
+        assert!(!SsoString::new().contains_char('a'));
+    }
+
+    #[test]
+    fn test_push_str_many() {
+        let mut s = SsoString::from("msg: ");
+        s.push_str_many(&["short", ", ", "then a few fragments", " that won't fit inline"]);
+        assert_eq!(s, "msg: short, then a few fragments that won't fit inline");
+        assert!(!s.is_inlined());
+
+        let mut inline = SsoString::new();
+        inline.push_str_many(&["a", "b", "c"]);
+        assert!(inline.is_inlined());
+        assert_eq!(inline, "abc");
+
+        // One growth for the whole batch, the same as a single reserve
+        let mut heap = SsoString::from("a heap string that will get a batch of parts");
+        let mut reserved = heap.clone();
+        reserved.reserve(100);
+        heap.push_str_many(&["x"; 100]);
+        assert_eq!(heap.capacity(), reserved.capacity());
+        assert_eq!(heap.len(), 44 + 100);
+
+        let mut stat = SsoString::from_static("a static string that must be copied first");
+        stat.push_str_many(&["!", "?"]);
+        assert!(!stat.is_static());
+        assert!(stat.ends_with("first!?"));
+    }
+
+    #[test]
+    fn test_cmp_ignore_ascii_case() {
+        use std::cmp::Ordering;
+
+        let s = SsoString::from("Hello World");
+        assert_eq!(s.cmp_ignore_ascii_case("hello world"), Ordering::Equal);
+        assert_eq!(s.cmp_ignore_ascii_case("HELLO"), Ordering::Greater);
+        assert_eq!(s.cmp_ignore_ascii_case("hello worlds"), Ordering::Less);
+        assert_eq!(s.cmp_ignore_ascii_case("help"), Ordering::Less);
+        assert_eq!(SsoString::from("Ä").cmp_ignore_ascii_case("ä"), "Ä".cmp("ä"));
+
+        let mut keys: Vec<SsoString> = ["banana", "Apple", "cherry", "apple pie", "BANANA split"]
+            .into_iter()
+            .map(SsoString::from)
+            .collect();
+        keys.sort_by(|a, b| a.cmp_ignore_ascii_case(b));
+        assert_eq!(keys, ["Apple", "apple pie", "banana", "BANANA split", "cherry"]);
+    }
+
+    #[test]
+    fn test_into_iterator_chars() {
+        let mut collected = String::new();
+        for c in SsoString::from("héllo") {
+            collected.push(c);
+        }
+        assert_eq!(collected, "héllo");
+
+        let heap = SsoString::from("a heap string with ünïcödé, consumed from both ends");
+        let mut chars = heap.clone().into_iter();
+        assert_eq!(chars.next(), Some('a'));
+        assert_eq!(chars.next_back(), Some('s'));
+        assert_eq!(chars.as_str(), " heap string with ünïcödé, consumed from both end");
+        assert_eq!(chars.clone().rev().collect::<String>(), chars.as_str().chars().rev().collect::<String>());
+        let (lo, hi) = chars.size_hint();
+        assert!(lo <= chars.clone().count() && hi == Some(chars.as_str().len()));
+        let remaining = chars.as_str().chars().count();
+        assert_eq!(chars.by_ref().count(), remaining);
+        assert_eq!(chars.next(), None);
+
+        let borrowed: String = (&heap).into_iter().filter(|c| !c.is_ascii()).collect();
+        assert_eq!(borrowed, "üïöé");
+        let stat = SsoString::from_static("static chars");
+        assert_eq!(stat.into_iter().filter(|&c| c == 'c').count(), 2);
+    }
+
+    #[test]
+    fn test_metadata_introspection() {
+        let inline = SsoString::from("inline");
+        assert_eq!(inline.raw_metadata(), 0b1000_0000 | 6);
+        assert_eq!(inline.decoded_metadata(), (true, false, 6));
+
+        let heap = SsoString::from("a string that is too long to be inlined");
+        assert_eq!(heap.raw_metadata(), 0);
+        let (is_inlined, is_static, _) = heap.decoded_metadata();
+        assert!(!is_inlined && !is_static);
+
+        let stat = SsoString::from_static("static, also too long to be stored inline");
+        let (is_inlined, is_static, _) = stat.decoded_metadata();
+        assert!(!is_inlined && is_static);
+        assert_eq!(stat.raw_metadata() & 0b0100_0000, 0b0100_0000);
+
+        let mut grown = SsoString::from(&"x".repeat(23)[..]);
+        assert_eq!(grown.decoded_metadata(), (true, false, 23));
+        grown.push('x');
+        assert_eq!(grown.raw_metadata(), 0);
+    }
+
+    #[test]
+    fn test_validate_after_random_operations() {
+        const STATICS: [&str; 3] = ["", "short static", "a static string long enough that it can't be inlined"];
+
+        let mut rng = XorShift(0x5eed_1234_abcd_9876);
+        for _ in 0..200 {
+            let mut s = SsoString::from_static(STATICS[(rng.next() % 3) as usize]);
+            let mut model = s.to_string();
+            for _ in 0..20 {
+                match rng.next() % 9 {
+                    0 | 1 => {
+                        let len = (rng.next() % 12) as usize;
+                        let piece = rng.string(len);
+                        s.push_str(&piece);
+                        model.push_str(&piece);
+                    }
+                    2 => {
+                        let cut = model.floor_char_boundary((rng.next() as usize) % (model.len() + 1));
+                        s.truncate(cut);
+                        model.truncate(cut);
+                    }
+                    3 => s = s.clone(),
+                    4 => s.reserve((rng.next() % 64) as usize),
+                    5 => s.shrink_or_inline(),
+                    6 => {
+                        let at = model.floor_char_boundary((rng.next() as usize) % (model.len() + 1));
+                        s.replace_range(at..at, "ins");
+                        model.insert_str(at, "ins");
+                    }
+                    7 => {
+                        let end = model.floor_char_boundary((rng.next() as usize) % (model.len() + 1));
+                        s.drain(..end);
+                        model.drain(..end);
+                    }
+                    _ => {
+                        s.make_mut();
+                    }
+                }
+                s.validate();
+                assert_eq!(s, model);
+            }
+        }
+    }
+
+    #[test]
+    fn test_swap_across_representations() {
+        static STATIC: &str = "a static string that stays shared after the swap";
+        let heap_text = "a heap string, too long to be stored inline";
+
+        let mut inline = SsoString::from("inline");
+        let mut heap = SsoString::from(heap_text);
+        let heap_ptr = heap.as_ptr();
+        inline.swap(&mut heap);
+        assert_eq!(inline.as_str(), heap_text);
+        assert_eq!(heap.as_str(), "inline");
+        assert_eq!(inline.as_ptr(), heap_ptr);
+        assert!(heap.is_inlined());
+
+        let mut stat = SsoString::from_static(STATIC);
+        heap.swap(&mut stat);
+        assert_eq!(heap.as_static(), Some(STATIC));
+        assert_eq!(stat, "inline");
+        stat.push_str(" and still writable");
+        assert_eq!(stat, "inline and still writable");
+
+        inline.validate();
+        heap.validate();
+        stat.validate();
+    }
+
+    #[test]
+    fn test_const_from_inline() {
+        const KEYWORDS: [SsoString; 3] = [
+            SsoString::from_inline("fn"),
+            SsoString::from_inline("ünïcödé"),
+            SsoString::from_inline("exactly 23 bytes long!!"),
+        ];
+        static WIDE: SsoStringN<40> = SsoStringN::from_inline("forty bytes fit inline in SsoStringN<40>");
+
+        assert_eq!(KEYWORDS, ["fn", "ünïcödé", "exactly 23 bytes long!!"]);
+        for keyword in &KEYWORDS {
+            assert!(keyword.is_inlined());
+            keyword.validate();
+            assert_eq!(keyword, &SsoString::from(keyword.as_str()));
+        }
+        assert_eq!(WIDE.len(), 40);
+        assert!(WIDE.is_inlined());
+
+        let mut copy = KEYWORDS[0].clone();
+        copy.push_str("_main");
+        assert_eq!(copy, "fn_main");
+        assert_eq!(KEYWORDS[0], "fn");
+    }
+
+    #[test]
+    #[should_panic(expected = "too long to be stored inline")]
+    fn test_from_inline_rejects_long_strings() {
+        let _ = SsoString::from_inline("this is longer than twenty-three bytes");
+    }
+
+    #[test]
+    fn test_squeeze() {
+        let mut path = SsoString::from("//usr///local//bin/");
+        path.squeeze('/');
+        assert_eq!(path, "/usr/local/bin/");
+
+        let mut spaced = SsoString::from("a  long   heap string    with  runs of spaces");
+        spaced.squeeze(' ');
+        assert_eq!(spaced, "a long heap string with runs of spaces");
+
+        let mut multi = SsoString::from("ééé-é--éé");
+        multi.squeeze('é');
+        assert_eq!(multi, "é-é--é");
+        multi.squeeze('-');
+        assert_eq!(multi, "é-é-é");
+
+        static STATIC: &str = "a static string with no doubled characters!";
+        let shared = SsoString::from_static(STATIC);
+        let squeezed = shared.squeezed('!');
+        assert_eq!(squeezed.as_static(), Some(STATIC));
+        let copied = SsoString::from_static("a static string that has a run!!! at the end").squeezed('!');
+        assert_eq!(copied, "a static string that has a run! at the end");
+        assert!(!copied.is_static());
+    }
+
+    #[test]
+    fn test_alternate_debug_output() {
+        let out = format!("{:#?}", SsoString::from("inline"));
+        assert!(out.starts_with("SsoString {"), "{out}");
+        assert!(out.contains("content: \"inline\""));
+        assert!(out.contains("is_inlined: true"));
+        assert!(format!("{:#?}", SsoStringN::<40>::from("wide")).starts_with("SsoString {"));
+    }
+}